@@ -1,101 +1,367 @@
 use aes::cipher::{generic_array::GenericArray, BlockDecrypt, BlockEncrypt, KeyInit};
 
-use crate::util::{pkcs7_pad, pkcs7_unpad};
-
-pub fn encrypt_aes128_cbc(bytes: &[u8], key: &[u8]) -> Vec<u8> {
-    let mut bytes = bytes.to_vec();
-    pkcs7_pad(&mut bytes, 16);
-
-    let key = GenericArray::clone_from_slice(key);
-    let cipher = aes::Aes128::new(&key);
-
-    let chunks: Vec<&[u8]> = bytes.chunks(16).collect();
-    let mut encrypted_blocks: Vec<Vec<u8>> = Vec::new();
-    let mut i = 0;
-    while i < chunks.len() {
-        let mut block = GenericArray::clone_from_slice(chunks[i]);
-        if i > 0 {
-            // XOR current plaintext block with previous encrypted block.
-            let previous_block = &encrypted_blocks[i - 1];
-            let xored = crate::util::xor_buffers(&block, &previous_block);
-            block = GenericArray::clone_from_slice(&xored);
+use crate::cipher::BlockCipher;
+pub use crate::cipher::CipherError;
+use crate::encodings::HexError;
+use crate::modes;
+pub use crate::modes::{CtrLayout, Endianness};
+use crate::padding::{Padding, Pkcs7};
+
+mod soft;
+
+pub use soft::{expand_key, gf_inverse, gf_mul, inverse_sbox, sbox, SoftAes, MAX_ROUNDS};
+
+/// The all-zero IV, for reproducing CBC ciphertexts produced without a real IV.
+pub const ZERO_IV: [u8; 16] = [0; 16];
+
+/// AES with the key size chosen by the length of the key: 16, 24 or 32 bytes for AES-128,
+/// AES-192 or AES-256.
+#[derive(Clone)]
+pub enum Aes {
+    Aes128(aes::Aes128),
+    Aes192(aes::Aes192),
+    Aes256(aes::Aes256),
+}
+
+impl Aes {
+    pub fn new(key: &[u8]) -> Result<Self, CipherError> {
+        match key.len() {
+            16 => Ok(Aes::Aes128(aes::Aes128::new(GenericArray::from_slice(key)))),
+            24 => Ok(Aes::Aes192(aes::Aes192::new(GenericArray::from_slice(key)))),
+            32 => Ok(Aes::Aes256(aes::Aes256::new(GenericArray::from_slice(key)))),
+            len => Err(CipherError::InvalidKeyLength(len)),
         }
-        cipher.encrypt_block(&mut block);
-        encrypted_blocks.push(block.to_vec());
-        i += 1;
     }
-
-    encrypted_blocks.iter().flatten().copied().collect()
 }
 
-pub fn decrypt_aes128_cbc(encrypted: &[u8], key: &[u8]) -> Vec<u8> {
-    let key = GenericArray::clone_from_slice(key);
-    let cipher = aes::Aes128::new(&key);
+impl BlockCipher for Aes {
+    fn block_size(&self) -> usize {
+        16
+    }
+
+    fn encrypt_block(&self, block: &mut [u8]) {
+        let block = GenericArray::from_mut_slice(block);
+        match self {
+            Aes::Aes128(cipher) => cipher.encrypt_block(block),
+            Aes::Aes192(cipher) => cipher.encrypt_block(block),
+            Aes::Aes256(cipher) => cipher.encrypt_block(block),
+        }
+    }
 
-    let chunks: Vec<&[u8]> = encrypted.chunks(16).collect();
-    let mut decrypted_blocks = Vec::new();
-    let mut i = chunks.len() - 1;
-    while i > 0 {
-        let mut block = GenericArray::clone_from_slice(chunks[i]);
-        // Decrypt the block.
-        cipher.decrypt_block(&mut block);
-        // XOR the decrypted chunk with the preceding, encrypted block.
-        let previous_block = chunks[i - 1];
-        let xored_block = crate::util::xor_buffers(&block, previous_block);
-        decrypted_blocks.push(xored_block);
-        i -= 1;
+    fn decrypt_block(&self, block: &mut [u8]) {
+        let block = GenericArray::from_mut_slice(block);
+        match self {
+            Aes::Aes128(cipher) => cipher.decrypt_block(block),
+            Aes::Aes192(cipher) => cipher.decrypt_block(block),
+            Aes::Aes256(cipher) => cipher.decrypt_block(block),
+        }
     }
+}
 
-    if i == 0 {
-        let mut block = GenericArray::clone_from_slice(chunks[i]);
-        cipher.decrypt_block(&mut block);
-        // First block isn't XORed with anything.
-        decrypted_blocks.push(block.to_vec());
+// The `aes128` functions only accept 16-byte keys; the `aes` ones accept any AES key size.
+fn require_aes128(key: &[u8]) -> Result<(), CipherError> {
+    if key.len() != 16 {
+        return Err(CipherError::InvalidKeyLength(key.len()));
     }
+    Ok(())
+}
+
+// Encrypt using AES in CBC mode with PKCS #7 padding. The first plaintext block is XORed with
+// `iv`, which must be 16 bytes and is not included in the output.
+pub fn encrypt_aes_cbc(bytes: &[u8], key: &[u8], iv: &[u8]) -> Result<Vec<u8>, CipherError> {
+    encrypt_aes_cbc_with_padding(bytes, key, iv, &Pkcs7)
+}
+
+pub fn encrypt_aes_cbc_with_padding(
+    bytes: &[u8],
+    key: &[u8],
+    iv: &[u8],
+    padding: &dyn Padding,
+) -> Result<Vec<u8>, CipherError> {
+    modes::cbc_encrypt(&Aes::new(key)?, padding, iv, bytes)
+}
+
+pub fn encrypt_aes128_cbc(bytes: &[u8], key: &[u8], iv: &[u8]) -> Result<Vec<u8>, CipherError> {
+    require_aes128(key)?;
+    encrypt_aes_cbc(bytes, key, iv)
+}
 
-    decrypted_blocks.reverse();
-    let mut decrypted_bytes = decrypted_blocks.iter().flatten().copied().collect();
-    pkcs7_unpad(&mut decrypted_bytes);
-    decrypted_bytes
+// Encrypt using AES in CBC mode under a freshly generated random IV, which is prepended to the
+// ciphertext.
+pub fn encrypt_aes_cbc_random_iv(bytes: &[u8], key: &[u8]) -> Result<Vec<u8>, CipherError> {
+    let iv: [u8; 16] = rand::random();
+    let mut encrypted = iv.to_vec();
+    encrypted.extend(encrypt_aes_cbc(bytes, key, &iv)?);
+    Ok(encrypted)
 }
 
-pub fn encrypt_aes128_ecb(bytes: &[u8], key: &[u8]) -> Vec<u8> {
-    let mut bytes = bytes.to_vec();
-    pkcs7_pad(&mut bytes, 16);
+pub fn encrypt_aes128_cbc_random_iv(bytes: &[u8], key: &[u8]) -> Result<Vec<u8>, CipherError> {
+    require_aes128(key)?;
+    encrypt_aes_cbc_random_iv(bytes, key)
+}
+
+pub fn decrypt_aes_cbc(encrypted: &[u8], key: &[u8], iv: &[u8]) -> Result<Vec<u8>, CipherError> {
+    decrypt_aes_cbc_with_padding(encrypted, key, iv, &Pkcs7)
+}
+
+pub fn decrypt_aes_cbc_with_padding(
+    encrypted: &[u8],
+    key: &[u8],
+    iv: &[u8],
+    padding: &dyn Padding,
+) -> Result<Vec<u8>, CipherError> {
+    modes::cbc_decrypt(&Aes::new(key)?, padding, iv, encrypted)
+}
 
-    let key = GenericArray::clone_from_slice(key);
-    let cipher = aes::Aes128::new(&key);
+pub fn decrypt_aes128_cbc(encrypted: &[u8], key: &[u8], iv: &[u8]) -> Result<Vec<u8>, CipherError> {
+    require_aes128(key)?;
+    decrypt_aes_cbc(encrypted, key, iv)
+}
 
-    let chunks: Vec<&[u8]> = bytes.chunks(16).collect();
-    let mut encrypted_blocks: Vec<Vec<u8>> = Vec::new();
-    for chunk in chunks {
-        let mut block = GenericArray::clone_from_slice(chunk);
-        cipher.encrypt_block(&mut block);
-        encrypted_blocks.push(block.to_vec());
+// Decrypt AES CBC ciphertext whose first block is the IV, as produced by
+// `encrypt_aes_cbc_random_iv`.
+pub fn decrypt_aes_cbc_prepended_iv(encrypted: &[u8], key: &[u8]) -> Result<Vec<u8>, CipherError> {
+    if encrypted.len() < 16 {
+        return Err(CipherError::InvalidCiphertextLength(encrypted.len()));
     }
+    let (iv, encrypted) = encrypted.split_at(16);
+    decrypt_aes_cbc(encrypted, key, iv)
+}
+
+pub fn decrypt_aes128_cbc_prepended_iv(
+    encrypted: &[u8],
+    key: &[u8],
+) -> Result<Vec<u8>, CipherError> {
+    require_aes128(key)?;
+    decrypt_aes_cbc_prepended_iv(encrypted, key)
+}
+
+// Encrypt using AES in ECB mode with PKCS #7 padding.
+pub fn encrypt_aes_ecb(bytes: &[u8], key: &[u8]) -> Result<Vec<u8>, CipherError> {
+    encrypt_aes_ecb_with_padding(bytes, key, &Pkcs7)
+}
+
+pub fn encrypt_aes_ecb_with_padding(
+    bytes: &[u8],
+    key: &[u8],
+    padding: &dyn Padding,
+) -> Result<Vec<u8>, CipherError> {
+    modes::ecb_encrypt(&Aes::new(key)?, padding, bytes)
+}
+
+pub fn encrypt_aes128_ecb(bytes: &[u8], key: &[u8]) -> Result<Vec<u8>, CipherError> {
+    require_aes128(key)?;
+    encrypt_aes_ecb(bytes, key)
+}
 
-    encrypted_blocks.iter().flatten().copied().collect()
+pub fn decrypt_aes_ecb(encrypted: &[u8], key: &[u8]) -> Result<Vec<u8>, CipherError> {
+    decrypt_aes_ecb_with_padding(encrypted, key, &Pkcs7)
 }
 
-pub fn decrypt_aes128_ecb(encrypted: &[u8], key: &[u8]) -> Vec<u8> {
-    let key = GenericArray::clone_from_slice(key);
-    let cipher = aes::Aes128::new(&key);
-    let chunks = encrypted.chunks(16);
-    let mut blocks = Vec::new();
-    for chunk in chunks {
-        blocks.push(GenericArray::clone_from_slice(chunk));
+pub fn decrypt_aes_ecb_with_padding(
+    encrypted: &[u8],
+    key: &[u8],
+    padding: &dyn Padding,
+) -> Result<Vec<u8>, CipherError> {
+    modes::ecb_decrypt(&Aes::new(key)?, padding, encrypted)
+}
+
+pub fn decrypt_aes128_ecb(encrypted: &[u8], key: &[u8]) -> Result<Vec<u8>, CipherError> {
+    require_aes128(key)?;
+    decrypt_aes_ecb(encrypted, key)
+}
+
+/// The AES-CTR keystream, generated one block at a time as it is consumed.
+///
+/// The counter wraps around silently once it overflows `counter_length` bytes.
+pub struct CtrKeystream(modes::CtrKeystream<Aes>);
+
+impl CtrKeystream {
+    pub fn new(key: &[u8], nonce: &[u8], layout: CtrLayout) -> Result<Self, CipherError> {
+        Ok(CtrKeystream(modes::CtrKeystream::new(
+            Aes::new(key)?,
+            nonce,
+            layout,
+        )?))
     }
-    cipher.decrypt_blocks(&mut blocks);
 
-    let mut decrypted_bytes = blocks.iter().flatten().copied().collect();
-    pkcs7_unpad(&mut decrypted_bytes);
-    decrypted_bytes
+    // Move to byte `offset` of the keystream, generating only the block that contains it.
+    pub fn seek(&mut self, offset: u64) {
+        self.0.seek(offset)
+    }
+}
+
+impl Iterator for CtrKeystream {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<u8> {
+        self.0.next()
+    }
+}
+
+// Encrypt using AES in CTR mode. No padding is needed, so the output is the same length as the
+// input.
+pub fn encrypt_aes_ctr(
+    bytes: &[u8],
+    key: &[u8],
+    nonce: &[u8],
+    layout: CtrLayout,
+) -> Result<Vec<u8>, CipherError> {
+    modes::ctr_apply(&Aes::new(key)?, nonce, layout, bytes)
+}
+
+pub fn encrypt_aes128_ctr(
+    bytes: &[u8],
+    key: &[u8],
+    nonce: &[u8],
+    layout: CtrLayout,
+) -> Result<Vec<u8>, CipherError> {
+    require_aes128(key)?;
+    encrypt_aes_ctr(bytes, key, nonce, layout)
+}
+
+// Replace the plaintext at `offset` in a CTR ciphertext with `new_plaintext`, recomputing the
+// keystream only for the blocks it covers, using the default layout. The ciphertext grows if the
+// new plaintext runs past its end.
+pub fn ctr_edit(
+    ciphertext: &[u8],
+    key: &[u8],
+    nonce: &[u8],
+    offset: usize,
+    new_plaintext: &[u8],
+) -> Result<Vec<u8>, CipherError> {
+    ctr_edit_with_layout(
+        ciphertext,
+        key,
+        nonce,
+        CtrLayout::default(),
+        offset,
+        new_plaintext,
+    )
+}
+
+pub fn ctr_edit_with_layout(
+    ciphertext: &[u8],
+    key: &[u8],
+    nonce: &[u8],
+    layout: CtrLayout,
+    offset: usize,
+    new_plaintext: &[u8],
+) -> Result<Vec<u8>, CipherError> {
+    if offset > ciphertext.len() {
+        return Err(CipherError::EditOutOfRange {
+            offset,
+            length: ciphertext.len(),
+        });
+    }
+
+    let mut keystream = CtrKeystream::new(key, nonce, layout)?;
+    keystream.seek(offset as u64);
+    let mut edited = ciphertext.to_vec();
+    for (i, (k, p)) in keystream.zip(new_plaintext).enumerate() {
+        match edited.get_mut(offset + i) {
+            Some(c) => *c = k ^ p,
+            None => edited.push(k ^ p),
+        }
+    }
+    Ok(edited)
 }
 
-pub fn detect_aes128_ecb(strings: &[&str]) -> String {
+// Decrypting is the same operation as encrypting.
+pub fn decrypt_aes_ctr(
+    encrypted: &[u8],
+    key: &[u8],
+    nonce: &[u8],
+    layout: CtrLayout,
+) -> Result<Vec<u8>, CipherError> {
+    encrypt_aes_ctr(encrypted, key, nonce, layout)
+}
+
+pub fn decrypt_aes128_ctr(
+    encrypted: &[u8],
+    key: &[u8],
+    nonce: &[u8],
+    layout: CtrLayout,
+) -> Result<Vec<u8>, CipherError> {
+    encrypt_aes128_ctr(encrypted, key, nonce, layout)
+}
+
+// Encrypt using AES in full-block CFB mode. No padding is needed, so the output is the same
+// length as the input.
+pub fn encrypt_aes_cfb(bytes: &[u8], key: &[u8], iv: &[u8]) -> Result<Vec<u8>, CipherError> {
+    modes::cfb_encrypt(&Aes::new(key)?, iv, bytes)
+}
+
+pub fn decrypt_aes_cfb(encrypted: &[u8], key: &[u8], iv: &[u8]) -> Result<Vec<u8>, CipherError> {
+    modes::cfb_decrypt(&Aes::new(key)?, iv, encrypted)
+}
+
+// Encrypt using AES in CFB-8 mode, which runs the block cipher once per byte.
+pub fn encrypt_aes_cfb8(bytes: &[u8], key: &[u8], iv: &[u8]) -> Result<Vec<u8>, CipherError> {
+    modes::cfb8_encrypt(&Aes::new(key)?, iv, bytes)
+}
+
+pub fn decrypt_aes_cfb8(encrypted: &[u8], key: &[u8], iv: &[u8]) -> Result<Vec<u8>, CipherError> {
+    modes::cfb8_decrypt(&Aes::new(key)?, iv, encrypted)
+}
+
+// Encrypt using AES in OFB mode. No padding is needed, so the output is the same length as the
+// input.
+pub fn encrypt_aes_ofb(bytes: &[u8], key: &[u8], iv: &[u8]) -> Result<Vec<u8>, CipherError> {
+    modes::ofb_apply(&Aes::new(key)?, iv, bytes)
+}
+
+// Decrypting is the same operation as encrypting.
+pub fn decrypt_aes_ofb(encrypted: &[u8], key: &[u8], iv: &[u8]) -> Result<Vec<u8>, CipherError> {
+    encrypt_aes_ofb(encrypted, key, iv)
+}
+
+// The OFB keystream depends only on the key and IV, so two messages encrypted under a reused IV
+// share it. Given one known plaintext and its ciphertext, recover as much of another ciphertext's
+// plaintext as the known message covers.
+pub fn recover_ofb_reused_iv(
+    known_plaintext: &[u8],
+    known_ciphertext: &[u8],
+    ciphertext: &[u8],
+) -> Vec<u8> {
+    known_plaintext
+        .iter()
+        .zip(known_ciphertext)
+        .zip(ciphertext)
+        .map(|((p, k), c)| p ^ k ^ c)
+        .collect()
+}
+
+// Under a reused IV, CFB-8 ciphertexts stay identical for as long as their plaintexts do, and the
+// first differing ciphertext byte is still produced from the same keystream byte. Returns the
+// length of the shared plaintext prefix, and the XOR of the two plaintext bytes where they first
+// differ.
+pub fn cfb8_reused_iv_leak(first: &[u8], second: &[u8]) -> (usize, Option<u8>) {
+    let common_prefix = first.iter().zip(second).take_while(|(a, b)| a == b).count();
+    let divergence = match (first.get(common_prefix), second.get(common_prefix)) {
+        (Some(a), Some(b)) => Some(a ^ b),
+        _ => None,
+    };
+    (common_prefix, divergence)
+}
+
+// Count the pairs of identical blocks in `bytes`. Under ECB, repeated plaintext blocks give
+// repeated ciphertext blocks, which other modes make vanishingly unlikely.
+pub fn repeated_blocks(bytes: &[u8], block_size: usize) -> usize {
+    let blocks: Vec<&[u8]> = bytes.chunks_exact(block_size).collect();
+    let mut count = 0;
+    for i in 0..blocks.len() {
+        count += blocks[i + 1..].iter().filter(|b| **b == blocks[i]).count();
+    }
+    count
+}
+
+// Pick the hex-encoded ciphertext whose 16-byte blocks are most alike, or `None` if there are
+// no ciphertexts.
+pub fn detect_aes128_ecb(strings: &[&str]) -> Result<Option<String>, HexError> {
     let mut average_distances: Vec<(u32, String)> = Vec::new();
     for s in strings {
-        let decoded = crate::encodings::hex_decode(s);
+        let decoded = crate::encodings::hex_decode(s)?;
         let chunks: Vec<&[u8]> = decoded.chunks(16).collect();
 
         let mut total_distances = 0;
@@ -105,20 +371,484 @@ pub fn detect_aes128_ecb(strings: &[&str]) -> String {
                 total_distances += distance;
             }
         }
-        average_distances.push((total_distances / (chunks.len() as u32), s.to_string()));
+        let average_distance = total_distances / (chunks.len().max(1) as u32);
+        average_distances.push((average_distance, s.to_string()));
     }
-    average_distances.sort_by(|a, b| a.0.cmp(&b.0));
-    average_distances.first().unwrap().1.clone()
+    average_distances.sort_by_key(|a| a.0);
+    Ok(average_distances.into_iter().next().map(|(_, s)| s))
 }
 
 #[cfg(test)]
 mod test {
+    const NIST_KEY: &str = "2b7e151628aed2a6abf7158809cf4f3c";
+    const NIST_IV: &str = "000102030405060708090a0b0c0d0e0f";
+    const NIST_PLAINTEXT: &str = "6bc1bee22e409f96e93d7e117393172aae2d8a571e03ac9c9eb76fac45af8e51\
+                                  30c81c46a35ce411e5fbc1191a0a52eff69f2445df4f9b17ad2b417be66c3710";
+    // NIST SP 800-38A, F.2.1 CBC-AES128.Encrypt.
+    const NIST_CBC_CIPHERTEXT: &str =
+        "7649abac8119b246cee98e9b12e9197d5086cb9b507219ee95db113a917678b2\
+         73bed6b8e3c1743b7116e69e222295163ff1caa1681fac09120eca307586e1a7";
+
     #[test]
     fn aes128_ebc_encrypt_decrypt() {
         let plaintext_bytes = "I hope you are having lots of fun in trying to catch me.".as_bytes();
         let key_bytes = &"YELLOW SUBMARINE".as_bytes();
-        let encrypted = crate::aes::encrypt_aes128_ecb(plaintext_bytes, key_bytes);
-        let decrypted = crate::aes::decrypt_aes128_ecb(&encrypted, &key_bytes);
+        let encrypted = crate::aes::encrypt_aes128_ecb(plaintext_bytes, key_bytes).unwrap();
+        let decrypted = crate::aes::decrypt_aes128_ecb(&encrypted, key_bytes).unwrap();
         assert_eq!(plaintext_bytes, decrypted);
     }
+
+    #[test]
+    fn aes128_cbc_nist_vectors() {
+        let key = crate::encodings::hex_decode(NIST_KEY).unwrap();
+        let iv = crate::encodings::hex_decode(NIST_IV).unwrap();
+        let plaintext = crate::encodings::hex_decode(NIST_PLAINTEXT).unwrap();
+        let encrypted = crate::aes::encrypt_aes128_cbc(&plaintext, &key, &iv).unwrap();
+        // The final block is PKCS #7 padding.
+        assert_eq!(80, encrypted.len());
+        assert_eq!(
+            NIST_CBC_CIPHERTEXT,
+            crate::encodings::hex_encode(&encrypted[..64])
+        );
+        assert_eq!(
+            plaintext,
+            crate::aes::decrypt_aes128_cbc(&encrypted, &key, &iv).unwrap()
+        );
+    }
+
+    #[test]
+    fn aes128_cbc_nist_vectors_without_padding() {
+        let key = crate::encodings::hex_decode(NIST_KEY).unwrap();
+        let iv = crate::encodings::hex_decode(NIST_IV).unwrap();
+        let plaintext = crate::encodings::hex_decode(NIST_PLAINTEXT).unwrap();
+        let padding = crate::padding::NoPadding;
+        let encrypted =
+            crate::aes::encrypt_aes_cbc_with_padding(&plaintext, &key, &iv, &padding).unwrap();
+        assert_eq!(
+            NIST_CBC_CIPHERTEXT,
+            crate::encodings::hex_encode(&encrypted)
+        );
+        assert_eq!(
+            plaintext,
+            crate::aes::decrypt_aes_cbc_with_padding(&encrypted, &key, &iv, &padding).unwrap()
+        );
+        assert_eq!(
+            Err(crate::aes::CipherError::Padding(
+                crate::padding::PaddingError::NotBlockAligned(63)
+            )),
+            crate::aes::encrypt_aes_cbc_with_padding(&plaintext[..63], &key, &iv, &padding)
+        );
+    }
+
+    #[test]
+    fn ecb_with_other_paddings() {
+        use crate::padding::{AnsiX923, Iso10126, Iso7816, Padding, ZeroPadding};
+
+        let key = "YELLOW SUBMARINE".as_bytes();
+        let plaintext = "I hope you are having lots of fun in trying to catch me.".as_bytes();
+        let schemes: [&dyn Padding; 4] = [&AnsiX923, &Iso7816, &Iso10126, &ZeroPadding];
+        for padding in schemes {
+            let encrypted =
+                crate::aes::encrypt_aes_ecb_with_padding(plaintext, key, padding).unwrap();
+            assert_eq!(64, encrypted.len());
+            assert_eq!(
+                plaintext,
+                crate::aes::decrypt_aes_ecb_with_padding(&encrypted, key, padding).unwrap()
+            );
+        }
+        // The default is PKCS #7, which pads empty input to a full block.
+        assert_eq!(16, crate::aes::encrypt_aes_ecb(&[], key).unwrap().len());
+    }
+
+    #[test]
+    fn decrypt_rejects_invalid_padding() {
+        use crate::aes::CipherError;
+        use crate::padding::PaddingError;
+
+        let key = "YELLOW SUBMARINE".as_bytes();
+        let iv = [0; 16];
+        // Encrypt data ending in a bad PKCS #7 padding byte without adding real padding.
+        let mut plaintext = [0x41; 32];
+        plaintext[31] = 0x20;
+        let padding = crate::padding::NoPadding;
+        let cbc = crate::aes::encrypt_aes_cbc_with_padding(&plaintext, key, &iv, &padding);
+        let ecb = crate::aes::encrypt_aes_ecb_with_padding(&plaintext, key, &padding);
+        let (cbc, ecb) = (cbc.unwrap(), ecb.unwrap());
+        assert_eq!(
+            Err(CipherError::Padding(PaddingError::InvalidPaddingLength(
+                0x20
+            ))),
+            crate::aes::decrypt_aes128_cbc(&cbc, key, &iv)
+        );
+        assert_eq!(
+            Err(CipherError::Padding(PaddingError::InvalidPaddingLength(
+                0x20
+            ))),
+            crate::aes::decrypt_aes128_ecb(&ecb, key)
+        );
+
+        plaintext[30] = 0x02;
+        plaintext[31] = 0x02;
+        let ecb = crate::aes::encrypt_aes_ecb_with_padding(&plaintext, key, &padding).unwrap();
+        assert_eq!(
+            plaintext[..30].to_vec(),
+            crate::aes::decrypt_aes128_ecb(&ecb, key).unwrap()
+        );
+        plaintext[30] = 0x03;
+        plaintext[31] = 0x03;
+        let ecb = crate::aes::encrypt_aes_ecb_with_padding(&plaintext, key, &padding).unwrap();
+        assert_eq!(
+            Err(CipherError::Padding(PaddingError::InvalidPaddingByte {
+                offset: 29
+            })),
+            crate::aes::decrypt_aes128_ecb(&ecb, key)
+        );
+
+        // The lenient scheme strips the padding anyway, as a broken server would.
+        assert_eq!(
+            plaintext[..29].to_vec(),
+            crate::aes::decrypt_aes_ecb_with_padding(&ecb, key, &crate::padding::Pkcs7Lenient)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn aes128_cbc_random_iv_is_prepended() {
+        let plaintext = "I hope you are having lots of fun in trying to catch me.".as_bytes();
+        let key = "YELLOW SUBMARINE".as_bytes();
+        let first = crate::aes::encrypt_aes128_cbc_random_iv(plaintext, key).unwrap();
+        let second = crate::aes::encrypt_aes128_cbc_random_iv(plaintext, key).unwrap();
+        assert_ne!(first, second);
+        assert_eq!(
+            crate::aes::encrypt_aes128_cbc(plaintext, key, &first[..16]).unwrap(),
+            &first[16..]
+        );
+        assert_eq!(
+            plaintext,
+            crate::aes::decrypt_aes128_cbc_prepended_iv(&first, key).unwrap()
+        );
+    }
+
+    #[test]
+    fn aes128_ctr_cryptopals_vector() {
+        let encrypted = crate::encodings::base64_decode(
+            "L77na/nrFsKvynd6HzOoG7GHTLXsTVu9qvY/2syLXzhPweyyMTJULu/6/kXX0KSvoOLSFQ==",
+        )
+        .unwrap();
+        let decrypted = crate::aes::decrypt_aes128_ctr(
+            &encrypted,
+            "YELLOW SUBMARINE".as_bytes(),
+            &[0; 8],
+            crate::aes::CtrLayout::default(),
+        )
+        .unwrap();
+        assert_eq!(
+            "Yo, VIP Let's kick it Ice, Ice, baby Ice, Ice, baby ",
+            std::str::from_utf8(&decrypted).unwrap()
+        );
+    }
+
+    #[test]
+    fn aes128_ctr_nist_vector() {
+        // NIST SP 800-38A, F.5.1 CTR-AES128.Encrypt, whose initial counter block is
+        // f0f1f2f3f4f5f6f7f8f9fafbfcfdfeff. Treat the first 14 bytes as the nonce and start the
+        // 16-bit big-endian counter at 0xfeff by skipping that many blocks.
+        let key = crate::encodings::hex_decode(NIST_KEY).unwrap();
+        let nonce = crate::encodings::hex_decode("f0f1f2f3f4f5f6f7f8f9fafbfcfd").unwrap();
+        let layout = crate::aes::CtrLayout {
+            nonce_length: 14,
+            counter_length: 2,
+            counter_endianness: crate::aes::Endianness::Big,
+        };
+        let keystream: Vec<u8> = crate::aes::CtrKeystream::new(&key, &nonce, layout)
+            .unwrap()
+            .skip(0xfeff * 16)
+            .take(32)
+            .collect();
+        let plaintext = crate::encodings::hex_decode(
+            "6bc1bee22e409f96e93d7e117393172aae2d8a571e03ac9c9eb76fac45af8e51",
+        )
+        .unwrap();
+        assert_eq!(
+            "874d6191b620e3261bef6864990db6ce9806f66b7970fdff8617187bb9fffdff",
+            crate::encodings::hex_encode(&crate::util::xor_buffers(&plaintext, &keystream))
+        );
+    }
+
+    #[test]
+    fn aes128_ctr_round_trip_without_padding() {
+        let plaintext = "I hope you are having lots of fun in trying to catch me.".as_bytes();
+        let key = "YELLOW SUBMARINE".as_bytes();
+        let nonce = [7; 12];
+        let layout = crate::aes::CtrLayout::NONCE_96;
+        let encrypted = crate::aes::encrypt_aes128_ctr(plaintext, key, &nonce, layout).unwrap();
+        assert_eq!(plaintext.len(), encrypted.len());
+        assert_eq!(
+            plaintext,
+            crate::aes::decrypt_aes128_ctr(&encrypted, key, &nonce, layout).unwrap()
+        );
+    }
+
+    #[test]
+    fn ctr_keystream_seek() {
+        let key = "YELLOW SUBMARINE".as_bytes();
+        let layout = crate::aes::CtrLayout::default();
+        let keystream: Vec<u8> = crate::aes::CtrKeystream::new(key, &[0; 8], layout)
+            .unwrap()
+            .take(100)
+            .collect();
+        for offset in [0, 1, 15, 16, 17, 63, 64, 99] {
+            let mut seeked = crate::aes::CtrKeystream::new(key, &[0; 8], layout).unwrap();
+            seeked.seek(offset as u64);
+            let rest: Vec<u8> = seeked.take(100 - offset).collect();
+            assert_eq!(&keystream[offset..], rest.as_slice());
+        }
+    }
+
+    #[test]
+    fn ctr_edit_matches_reencryption() {
+        let key = "YELLOW SUBMARINE".as_bytes();
+        let nonce = [1; 8];
+        let layout = crate::aes::CtrLayout::default();
+        let plaintext = "I hope you are having lots of fun in trying to catch me.".as_bytes();
+        let encrypted = crate::aes::encrypt_aes128_ctr(plaintext, key, &nonce, layout).unwrap();
+
+        let edited =
+            crate::aes::ctr_edit(&encrypted, key, &nonce, 15, "HAVING".as_bytes()).unwrap();
+        let mut expected = plaintext.to_vec();
+        expected[15..21].copy_from_slice("HAVING".as_bytes());
+        assert_eq!(
+            crate::aes::encrypt_aes128_ctr(&expected, key, &nonce, layout).unwrap(),
+            edited
+        );
+
+        let extended =
+            crate::aes::ctr_edit(&encrypted, key, &nonce, 50, "me, and more".as_bytes()).unwrap();
+        assert_eq!(
+            "I hope you are having lots of fun in trying to catme, and more".as_bytes(),
+            crate::aes::decrypt_aes128_ctr(&extended, key, &nonce, layout).unwrap()
+        );
+    }
+
+    #[test]
+    fn fips197_known_answers() {
+        use crate::cipher::BlockCipher;
+
+        // FIPS-197 Appendix C.1, C.2 and C.3, checked against both implementations.
+        let plaintext = crate::encodings::hex_decode("00112233445566778899aabbccddeeff").unwrap();
+        let vectors = [
+            (16, "69c4e0d86a7b0430d8cdb78070b4c55a"),
+            (24, "dda97ca4864cdfe06eaf70a0ec0d7191"),
+            (32, "8ea2b7ca516745bfeafc49904b496089"),
+        ];
+        for (key_length, expected) in vectors {
+            let key: Vec<u8> = (0..key_length).collect();
+            let aes = crate::aes::Aes::new(&key).unwrap();
+            let soft = crate::aes::SoftAes::new(&key).unwrap();
+            for cipher in [&aes as &dyn BlockCipher, &soft] {
+                let mut block = plaintext.clone();
+                cipher.encrypt_block(&mut block);
+                assert_eq!(expected, crate::encodings::hex_encode(&block));
+                cipher.decrypt_block(&mut block);
+                assert_eq!(plaintext, block);
+            }
+
+            let encrypted = crate::aes::encrypt_aes_ecb(&plaintext, &key).unwrap();
+            assert_eq!(expected, crate::encodings::hex_encode(&encrypted[..16]));
+            assert_eq!(
+                plaintext,
+                crate::aes::decrypt_aes_ecb(&encrypted, &key).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn aes256_cbc_and_ctr_round_trip() {
+        let plaintext = "I hope you are having lots of fun in trying to catch me.".as_bytes();
+        let key = [0x42; 32];
+        let iv = [0x24; 16];
+        let encrypted = crate::aes::encrypt_aes_cbc(plaintext, &key, &iv).unwrap();
+        assert_eq!(
+            plaintext,
+            crate::aes::decrypt_aes_cbc(&encrypted, &key, &iv).unwrap()
+        );
+
+        let layout = crate::aes::CtrLayout::default();
+        let encrypted = crate::aes::encrypt_aes_ctr(plaintext, &key, &[0; 8], layout).unwrap();
+        assert_eq!(
+            plaintext,
+            crate::aes::decrypt_aes_ctr(&encrypted, &key, &[0; 8], layout).unwrap()
+        );
+    }
+
+    #[test]
+    fn malformed_inputs_are_errors() {
+        use crate::aes::CipherError;
+
+        let key = "YELLOW SUBMARINE".as_bytes();
+        let iv = crate::aes::ZERO_IV;
+        assert_eq!(
+            Err(CipherError::InvalidKeyLength(15)),
+            crate::aes::decrypt_aes128_cbc(&[0; 32], &key[..15], &iv)
+        );
+        assert_eq!(
+            Err(CipherError::InvalidCiphertextLength(20)),
+            crate::aes::decrypt_aes128_cbc(&[0; 20], key, &iv)
+        );
+        assert_eq!(
+            Err(CipherError::InvalidCiphertextLength(31)),
+            crate::aes::decrypt_aes128_ecb(&[0; 31], key)
+        );
+        assert_eq!(
+            Err(CipherError::InvalidCiphertextLength(0)),
+            crate::aes::decrypt_aes128_cbc(&[], key, &iv)
+        );
+        assert_eq!(
+            Err(CipherError::InvalidCiphertextLength(0)),
+            crate::aes::decrypt_aes128_ecb(&[], key)
+        );
+        assert_eq!(
+            Err(CipherError::InvalidIvLength(8)),
+            crate::aes::encrypt_aes128_cbc(key, key, &iv[..8])
+        );
+        assert_eq!(
+            Err(CipherError::InvalidCiphertextLength(10)),
+            crate::aes::decrypt_aes128_cbc_prepended_iv(&[0; 10], key)
+        );
+        assert_eq!(
+            Err(CipherError::InvalidIvLength(17)),
+            crate::aes::encrypt_aes_ofb(key, key, &[0; 17])
+        );
+        assert_eq!(
+            Err(CipherError::InvalidIvLength(12)),
+            crate::aes::encrypt_aes_ctr(key, key, &[0; 12], crate::aes::CtrLayout::CRYPTOPALS)
+        );
+        let layout = crate::aes::CtrLayout {
+            nonce_length: 16,
+            counter_length: 0,
+            counter_endianness: crate::aes::Endianness::Big,
+        };
+        assert_eq!(
+            Err(CipherError::InvalidCtrLayout(layout)),
+            crate::aes::encrypt_aes_ctr(key, key, &[0; 16], layout)
+        );
+        assert_eq!(
+            Err(CipherError::EditOutOfRange {
+                offset: 17,
+                length: 16
+            }),
+            crate::aes::ctr_edit(&[0; 16], key, &[0; 8], 17, b"x")
+        );
+    }
+
+    #[test]
+    fn repeated_blocks() {
+        let key = "YELLOW SUBMARINE".as_bytes();
+        let mut plaintext = [b'A'; 64].to_vec();
+        plaintext.extend_from_slice("once".as_bytes());
+        let ecb = crate::aes::encrypt_aes128_ecb(&plaintext, key).unwrap();
+        assert_eq!(6, crate::aes::repeated_blocks(&ecb, 16));
+        let cbc = crate::aes::encrypt_aes128_cbc(&plaintext, key, &[1; 16]).unwrap();
+        assert_eq!(0, crate::aes::repeated_blocks(&cbc, 16));
+    }
+
+    #[test]
+    fn invalid_key_lengths() {
+        use crate::aes::CipherError;
+
+        let plaintext = "YELLOW SUBMARINE".as_bytes();
+        assert!(matches!(
+            crate::aes::Aes::new(&[0; 15]),
+            Err(CipherError::InvalidKeyLength(15))
+        ));
+        assert_eq!(
+            Err(CipherError::InvalidKeyLength(17)),
+            crate::aes::encrypt_aes_ecb(plaintext, &[0; 17])
+        );
+        // The AES-128 functions reject the larger key sizes.
+        assert_eq!(
+            Err(CipherError::InvalidKeyLength(32)),
+            crate::aes::encrypt_aes128_cbc(plaintext, &[0; 32], &crate::aes::ZERO_IV)
+        );
+    }
+
+    #[test]
+    fn aes128_cfb_nist_vectors() {
+        // NIST SP 800-38A, F.3.13 CFB128-AES128.Encrypt.
+        let key = crate::encodings::hex_decode(NIST_KEY).unwrap();
+        let iv = crate::encodings::hex_decode(NIST_IV).unwrap();
+        let plaintext = crate::encodings::hex_decode(NIST_PLAINTEXT).unwrap();
+        let expected = "3b3fd92eb72dad20333449f8e83cfb4ac8a64537a0b3a93fcde3cdad9f1ce58b\
+                        26751f67a3cbb140b1808cf187a4f4dfc04b05357c5d1c0eeac4c66f9ff7f2e6";
+        let encrypted = crate::aes::encrypt_aes_cfb(&plaintext, &key, &iv).unwrap();
+        assert_eq!(expected, crate::encodings::hex_encode(&encrypted));
+        assert_eq!(
+            plaintext,
+            crate::aes::decrypt_aes_cfb(&encrypted, &key, &iv).unwrap()
+        );
+
+        // A trailing partial block is truncated rather than padded.
+        let encrypted = crate::aes::encrypt_aes_cfb(&plaintext[..20], &key, &iv).unwrap();
+        assert_eq!(&expected[..40], crate::encodings::hex_encode(&encrypted));
+    }
+
+    #[test]
+    fn aes128_cfb8_nist_vectors() {
+        // NIST SP 800-38A, F.3.7 CFB8-AES128.Encrypt.
+        let key = crate::encodings::hex_decode(NIST_KEY).unwrap();
+        let iv = crate::encodings::hex_decode(NIST_IV).unwrap();
+        let plaintext =
+            crate::encodings::hex_decode("6bc1bee22e409f96e93d7e117393172aae2d").unwrap();
+        let encrypted = crate::aes::encrypt_aes_cfb8(&plaintext, &key, &iv).unwrap();
+        assert_eq!(
+            "3b79424c9c0dd436bace9e0ed4586a4f32b9",
+            crate::encodings::hex_encode(&encrypted)
+        );
+        assert_eq!(
+            plaintext,
+            crate::aes::decrypt_aes_cfb8(&encrypted, &key, &iv).unwrap()
+        );
+    }
+
+    #[test]
+    fn aes128_ofb_nist_vectors() {
+        // NIST SP 800-38A, F.4.1 OFB-AES128.Encrypt.
+        let key = crate::encodings::hex_decode(NIST_KEY).unwrap();
+        let iv = crate::encodings::hex_decode(NIST_IV).unwrap();
+        let plaintext = crate::encodings::hex_decode(NIST_PLAINTEXT).unwrap();
+        let expected = "3b3fd92eb72dad20333449f8e83cfb4a7789508d16918f03f53c52dac54ed825\
+                        9740051e9c5fecf64344f7a82260edcc304c6528f659c77866a510d9c1d6ae5e";
+        let encrypted = crate::aes::encrypt_aes_ofb(&plaintext, &key, &iv).unwrap();
+        assert_eq!(expected, crate::encodings::hex_encode(&encrypted));
+        assert_eq!(
+            plaintext,
+            crate::aes::decrypt_aes_ofb(&encrypted, &key, &iv).unwrap()
+        );
+    }
+
+    #[test]
+    fn ofb_reused_iv_leaks_plaintext() {
+        let key = "YELLOW SUBMARINE".as_bytes();
+        let iv = [5; 16];
+        let known = "I hope you are having lots of fun in trying to catch me.".as_bytes();
+        let secret = "Attack at dawn, and bring the documents along.".as_bytes();
+        let known_ciphertext = crate::aes::encrypt_aes_ofb(known, key, &iv).unwrap();
+        let ciphertext = crate::aes::encrypt_aes_ofb(secret, key, &iv).unwrap();
+        assert_eq!(
+            secret,
+            crate::aes::recover_ofb_reused_iv(known, &known_ciphertext, &ciphertext)
+        );
+    }
+
+    #[test]
+    fn cfb8_reused_iv_leaks_prefix() {
+        let key = "YELLOW SUBMARINE".as_bytes();
+        let iv = [5; 16];
+        let first = crate::aes::encrypt_aes_cfb8("transfer $100 to alice".as_bytes(), key, &iv);
+        let second = crate::aes::encrypt_aes_cfb8("transfer $900 to bob".as_bytes(), key, &iv);
+        assert_eq!(
+            (10, Some(b'1' ^ b'9')),
+            crate::aes::cfb8_reused_iv_leak(&first.unwrap(), &second.unwrap())
+        );
+    }
 }
@@ -0,0 +1,382 @@
+use std::sync::OnceLock;
+
+use crate::cipher::{BlockCipher, CipherError};
+
+// Multiply in GF(2^8) modulo the AES polynomial x^8 + x^4 + x^3 + x + 1.
+pub fn gf_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0;
+    while b != 0 {
+        if b & 1 != 0 {
+            product ^= a;
+        }
+        let carry = a & 0x80;
+        a <<= 1;
+        if carry != 0 {
+            a ^= 0x1b;
+        }
+        b >>= 1;
+    }
+    product
+}
+
+// The multiplicative inverse in GF(2^8), computed as a^254. Zero maps to zero, as in the S-box.
+pub fn gf_inverse(a: u8) -> u8 {
+    let mut result = 1;
+    let mut base = a;
+    let mut exponent = 254;
+    while exponent != 0 {
+        if exponent & 1 != 0 {
+            result = gf_mul(result, base);
+        }
+        base = gf_mul(base, base);
+        exponent >>= 1;
+    }
+    result
+}
+
+// The AES S-box, generated from the field inverse followed by the affine transformation.
+pub fn sbox() -> &'static [u8; 256] {
+    static SBOX: OnceLock<[u8; 256]> = OnceLock::new();
+    SBOX.get_or_init(|| {
+        let mut sbox = [0; 256];
+        for (i, s) in sbox.iter_mut().enumerate() {
+            let b = gf_inverse(i as u8);
+            *s = b
+                ^ b.rotate_left(1)
+                ^ b.rotate_left(2)
+                ^ b.rotate_left(3)
+                ^ b.rotate_left(4)
+                ^ 0x63;
+        }
+        sbox
+    })
+}
+
+pub fn inverse_sbox() -> &'static [u8; 256] {
+    static INVERSE_SBOX: OnceLock<[u8; 256]> = OnceLock::new();
+    INVERSE_SBOX.get_or_init(|| {
+        let mut inverse = [0; 256];
+        for (i, s) in sbox().iter().enumerate() {
+            inverse[*s as usize] = i as u8;
+        }
+        inverse
+    })
+}
+
+// The standard number of rounds for a key length, or `None` if it isn't an AES key.
+fn standard_rounds(key_length: usize) -> Option<usize> {
+    match key_length {
+        16 => Some(10),
+        24 => Some(12),
+        32 => Some(14),
+        _ => None,
+    }
+}
+
+// Most rounds `expand_key` accepts, far more than any analysis needs, so the schedule's size
+// can't overflow or exhaust memory.
+pub const MAX_ROUNDS: usize = 255;
+
+// Expand `key` into `rounds + 1` round keys using the FIPS-197 key schedule, which extends
+// naturally to any number of rounds up to `MAX_ROUNDS`.
+pub fn expand_key(key: &[u8], rounds: usize) -> Result<Vec<[u8; 16]>, CipherError> {
+    if standard_rounds(key.len()).is_none() {
+        return Err(CipherError::InvalidKeyLength(key.len()));
+    }
+    if rounds == 0 || rounds > MAX_ROUNDS {
+        return Err(CipherError::InvalidRoundCount(rounds));
+    }
+
+    let sbox = sbox();
+    let nk = key.len() / 4;
+    let mut words: Vec<[u8; 4]> = key.chunks(4).map(|w| [w[0], w[1], w[2], w[3]]).collect();
+    let mut rcon = 1;
+    for i in nk..4 * (rounds + 1) {
+        let mut temp = words[i - 1];
+        if i % nk == 0 {
+            temp.rotate_left(1);
+            temp = temp.map(|b| sbox[b as usize]);
+            temp[0] ^= rcon;
+            rcon = gf_mul(rcon, 2);
+        } else if nk > 6 && i % nk == 4 {
+            temp = temp.map(|b| sbox[b as usize]);
+        }
+        let previous = words[i - nk];
+        words.push([0, 1, 2, 3].map(|j| previous[j] ^ temp[j]));
+    }
+
+    Ok(words
+        .chunks(4)
+        .take(rounds + 1)
+        .map(|w| {
+            let mut round_key = [0; 16];
+            for (j, word) in w.iter().enumerate() {
+                round_key[4 * j..4 * j + 4].copy_from_slice(word);
+            }
+            round_key
+        })
+        .collect())
+}
+
+// The state is stored column by column, as the block bytes are, so byte `r + 4c` is row `r` of
+// column `c`.
+fn add_round_key(state: &mut [u8], round_key: &[u8; 16]) {
+    for (s, k) in state.iter_mut().zip(round_key) {
+        *s ^= k;
+    }
+}
+
+fn sub_bytes(state: &mut [u8], table: &[u8; 256]) {
+    for s in state.iter_mut() {
+        *s = table[*s as usize];
+    }
+}
+
+// Row `r` rotates left by `r` columns.
+fn shift_rows(state: &mut [u8]) {
+    let old: [u8; 16] = state.try_into().unwrap();
+    for r in 0..4 {
+        for c in 0..4 {
+            state[r + 4 * c] = old[r + 4 * ((c + r) % 4)];
+        }
+    }
+}
+
+fn inverse_shift_rows(state: &mut [u8]) {
+    let old: [u8; 16] = state.try_into().unwrap();
+    for r in 0..4 {
+        for c in 0..4 {
+            state[r + 4 * ((c + r) % 4)] = old[r + 4 * c];
+        }
+    }
+}
+
+// Multiply each column by the circulant matrix with first row `coefficients`.
+fn mix_columns_with(state: &mut [u8], coefficients: [u8; 4]) {
+    for column in state.chunks_mut(4) {
+        let old = [column[0], column[1], column[2], column[3]];
+        for (r, b) in column.iter_mut().enumerate() {
+            *b = (0..4).fold(0, |acc, i| {
+                acc ^ gf_mul(coefficients[(4 + i - r) % 4], old[i])
+            });
+        }
+    }
+}
+
+fn mix_columns(state: &mut [u8]) {
+    mix_columns_with(state, [2, 3, 1, 1]);
+}
+
+fn inverse_mix_columns(state: &mut [u8]) {
+    mix_columns_with(state, [14, 11, 13, 9]);
+}
+
+/// AES implemented from its definition, for experiments the `aes` crate can't support: the
+/// round count is configurable, the key schedule can be inspected, and encryption can be traced
+/// round by round.
+///
+/// As in the full cipher, the final round of a reduced-round variant omits MixColumns.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SoftAes {
+    round_keys: Vec<[u8; 16]>,
+}
+
+impl SoftAes {
+    // AES with the standard number of rounds for the key length: 10, 12 or 14.
+    pub fn new(key: &[u8]) -> Result<Self, CipherError> {
+        let rounds = standard_rounds(key.len()).ok_or(CipherError::InvalidKeyLength(key.len()))?;
+        Self::with_rounds(key, rounds)
+    }
+
+    pub fn with_rounds(key: &[u8], rounds: usize) -> Result<Self, CipherError> {
+        Ok(SoftAes {
+            round_keys: expand_key(key, rounds)?,
+        })
+    }
+
+    pub fn rounds(&self) -> usize {
+        self.round_keys.len() - 1
+    }
+
+    // The round keys, starting with the whitening key added before the first round.
+    pub fn round_keys(&self) -> &[[u8; 16]] {
+        &self.round_keys
+    }
+
+    // Encrypt a block in place, returning the state after the initial key addition and after each
+    // round. The last entry is the ciphertext.
+    pub fn encrypt_block_traced(&self, block: &mut [u8]) -> Vec<[u8; 16]> {
+        let sbox = sbox();
+        let rounds = self.rounds();
+        let mut trace = Vec::with_capacity(rounds + 1);
+
+        add_round_key(block, &self.round_keys[0]);
+        trace.push(block.try_into().unwrap());
+        for round in 1..=rounds {
+            sub_bytes(block, sbox);
+            shift_rows(block);
+            if round != rounds {
+                mix_columns(block);
+            }
+            add_round_key(block, &self.round_keys[round]);
+            trace.push(block.try_into().unwrap());
+        }
+        trace
+    }
+}
+
+impl BlockCipher for SoftAes {
+    fn block_size(&self) -> usize {
+        16
+    }
+
+    fn encrypt_block(&self, block: &mut [u8]) {
+        self.encrypt_block_traced(block);
+    }
+
+    fn decrypt_block(&self, block: &mut [u8]) {
+        let inverse_sbox = inverse_sbox();
+        let rounds = self.rounds();
+
+        add_round_key(block, &self.round_keys[rounds]);
+        for round in (1..=rounds).rev() {
+            if round != rounds {
+                inverse_mix_columns(block);
+            }
+            inverse_shift_rows(block);
+            sub_bytes(block, inverse_sbox);
+            add_round_key(block, &self.round_keys[round - 1]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::aes::SoftAes;
+    use crate::cipher::{BlockCipher, CipherError};
+
+    #[test]
+    fn field_arithmetic() {
+        // FIPS-197 section 4.2.
+        assert_eq!(0xc1, crate::aes::gf_mul(0x57, 0x83));
+        assert_eq!(0xfe, crate::aes::gf_mul(0x57, 0x13));
+        for a in 1..=255 {
+            assert_eq!(1, crate::aes::gf_mul(a, crate::aes::gf_inverse(a)));
+        }
+    }
+
+    #[test]
+    fn generated_sbox() {
+        let sbox = crate::aes::sbox();
+        let inverse = crate::aes::inverse_sbox();
+        assert_eq!(0x63, sbox[0x00]);
+        assert_eq!(0x7c, sbox[0x01]);
+        assert_eq!(0xed, sbox[0x53]);
+        assert_eq!(0x16, sbox[0xff]);
+        for i in 0..=255u8 {
+            assert_eq!(i, inverse[sbox[i as usize] as usize]);
+        }
+    }
+
+    #[test]
+    fn fips197_key_expansion() {
+        // FIPS-197 Appendix A.1 and A.3.
+        let key = crate::encodings::hex_decode("2b7e151628aed2a6abf7158809cf4f3c").unwrap();
+        let round_keys = crate::aes::expand_key(&key, 10).unwrap();
+        assert_eq!(11, round_keys.len());
+        assert_eq!(
+            "a0fafe1788542cb123a339392a6c7605",
+            crate::encodings::hex_encode(&round_keys[1])
+        );
+        assert_eq!(
+            "d014f9a8c9ee2589e13f0cc8b6630ca6",
+            crate::encodings::hex_encode(&round_keys[10])
+        );
+
+        let key = crate::encodings::hex_decode(
+            "603deb1015ca71be2b73aef0857d77811f352c073b6108d72d9810a30914dff4",
+        )
+        .unwrap();
+        let round_keys = crate::aes::expand_key(&key, 14).unwrap();
+        assert_eq!(
+            "fe4890d1e6188d0b046df344706c631e",
+            crate::encodings::hex_encode(&round_keys[14])
+        );
+    }
+
+    #[test]
+    fn matches_aes_crate() {
+        for key_length in [16, 24, 32] {
+            for _ in 0..50 {
+                let key: Vec<u8> = (0..key_length).map(|_| rand::random()).collect();
+                let block: [u8; 16] = rand::random();
+                let soft = SoftAes::new(&key).unwrap();
+                let reference = crate::aes::Aes::new(&key).unwrap();
+
+                let (mut a, mut b) = (block, block);
+                soft.encrypt_block(&mut a);
+                reference.encrypt_block(&mut b);
+                assert_eq!(b, a);
+                soft.decrypt_block(&mut a);
+                reference.decrypt_block(&mut b);
+                assert_eq!(block, a);
+                assert_eq!(block, b);
+            }
+        }
+    }
+
+    #[test]
+    fn ecb_through_modes() {
+        let key = "YELLOW SUBMARINE".as_bytes();
+        let plaintext = "I hope you are having lots of fun in trying to catch me.".as_bytes();
+        let soft = SoftAes::new(key).unwrap();
+        let padding = crate::padding::Pkcs7;
+        assert_eq!(
+            crate::aes::encrypt_aes128_ecb(plaintext, key).unwrap(),
+            crate::modes::ecb_encrypt(&soft, &padding, plaintext).unwrap()
+        );
+    }
+
+    #[test]
+    fn reduced_rounds() {
+        let key = "YELLOW SUBMARINE".as_bytes();
+        let plaintext: [u8; 16] = rand::random();
+        let full = SoftAes::new(key).unwrap();
+        let reduced = SoftAes::with_rounds(key, 4).unwrap();
+        assert_eq!(10, full.rounds());
+        assert_eq!(4, reduced.rounds());
+        // The reduced key schedule is a prefix of the full one.
+        assert_eq!(&full.round_keys()[..5], reduced.round_keys());
+
+        let mut block = plaintext;
+        let trace = reduced.encrypt_block_traced(&mut block);
+        assert_eq!(5, trace.len());
+        assert_eq!(block, trace[4]);
+        // Every round but the last matches the full cipher.
+        let mut full_block = plaintext;
+        let full_trace = full.encrypt_block_traced(&mut full_block);
+        assert_eq!(full_trace[..4], trace[..4]);
+        assert_ne!(full_trace[4], trace[4]);
+
+        reduced.decrypt_block(&mut block);
+        assert_eq!(plaintext, block);
+
+        assert_eq!(
+            Err(CipherError::InvalidRoundCount(0)),
+            SoftAes::with_rounds(key, 0)
+        );
+        assert_eq!(255, SoftAes::with_rounds(key, 255).unwrap().rounds());
+        assert_eq!(
+            Err(CipherError::InvalidRoundCount(256)),
+            SoftAes::with_rounds(key, 256)
+        );
+        assert_eq!(
+            Err(CipherError::InvalidRoundCount(usize::MAX)),
+            SoftAes::with_rounds(key, usize::MAX)
+        );
+        assert_eq!(
+            Err(CipherError::InvalidKeyLength(20)),
+            SoftAes::new(&[0; 20])
+        );
+    }
+}
@@ -0,0 +1,121 @@
+use std::fmt;
+
+use crate::modes::CtrLayout;
+use crate::padding::PaddingError;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CipherError {
+    /// A key whose length doesn't select a supported cipher.
+    InvalidKeyLength(usize),
+    /// An IV or nonce of unsupported length.
+    InvalidIvLength(usize),
+    /// Ciphertext whose length the mode can't decrypt, such as a partial final block.
+    InvalidCiphertextLength(usize),
+    /// A CTR layout whose nonce and counter don't exactly fill a block, or whose counter is
+    /// wider than 128 bits.
+    InvalidCtrLayout(CtrLayout),
+    /// A round count the cipher can't be built with.
+    InvalidRoundCount(usize),
+    /// An edit starting past the end of the ciphertext.
+    EditOutOfRange { offset: usize, length: usize },
+    /// An authentication tag length the mode doesn't allow.
+    InvalidTagLength(usize),
+    /// Invalid padding in decrypted data, or data the padding scheme can't pad.
+    Padding(PaddingError),
+    /// The authentication tag didn't match; no plaintext is released.
+    AuthenticationFailed,
+}
+
+impl fmt::Display for CipherError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CipherError::InvalidKeyLength(len) => write!(f, "invalid key length ({} bytes)", len),
+            CipherError::InvalidIvLength(len) => write!(f, "invalid IV length ({} bytes)", len),
+            CipherError::InvalidCiphertextLength(len) => {
+                write!(f, "invalid ciphertext length ({} bytes)", len)
+            }
+            CipherError::InvalidCtrLayout(layout) => write!(
+                f,
+                "invalid CTR layout ({}-byte nonce, {}-byte counter)",
+                layout.nonce_length, layout.counter_length
+            ),
+            CipherError::InvalidRoundCount(rounds) => write!(f, "invalid round count {}", rounds),
+            CipherError::EditOutOfRange { offset, length } => write!(
+                f,
+                "edit offset {} is past the end of {} bytes of ciphertext",
+                offset, length
+            ),
+            CipherError::InvalidTagLength(len) => write!(f, "invalid tag length ({} bytes)", len),
+            CipherError::Padding(error) => write!(f, "{}", error),
+            CipherError::AuthenticationFailed => write!(f, "authentication failed"),
+        }
+    }
+}
+
+impl std::error::Error for CipherError {}
+
+impl From<PaddingError> for CipherError {
+    fn from(error: PaddingError) -> Self {
+        CipherError::Padding(error)
+    }
+}
+
+/// A block cipher: a keyed permutation on fixed-size blocks.
+///
+/// The mode implementations in `modes` are generic over this trait, so attacks can be run
+/// against AES, toy ciphers with other block sizes, or deliberately weakened ciphers.
+pub trait BlockCipher {
+    fn block_size(&self) -> usize;
+
+    // Encrypt a single block in place. `block` must be exactly `block_size` bytes.
+    fn encrypt_block(&self, block: &mut [u8]);
+
+    // Decrypt a single block in place. `block` must be exactly `block_size` bytes.
+    fn decrypt_block(&self, block: &mut [u8]);
+}
+
+impl<C: BlockCipher + ?Sized> BlockCipher for &C {
+    fn block_size(&self) -> usize {
+        (**self).block_size()
+    }
+
+    fn encrypt_block(&self, block: &mut [u8]) {
+        (**self).encrypt_block(block)
+    }
+
+    fn decrypt_block(&self, block: &mut [u8]) {
+        (**self).decrypt_block(block)
+    }
+}
+
+/// A deliberately insecure cipher that XORs each block with the key, whose length sets the
+/// block size. Useful for testing modes and attacks with small or unusual block sizes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct XorCipher {
+    key: Vec<u8>,
+}
+
+impl XorCipher {
+    pub fn new(key: &[u8]) -> Result<Self, CipherError> {
+        if key.is_empty() {
+            return Err(CipherError::InvalidKeyLength(0));
+        }
+        Ok(XorCipher { key: key.to_vec() })
+    }
+}
+
+impl BlockCipher for XorCipher {
+    fn block_size(&self) -> usize {
+        self.key.len()
+    }
+
+    fn encrypt_block(&self, block: &mut [u8]) {
+        for (b, k) in block.iter_mut().zip(&self.key) {
+            *b ^= k;
+        }
+    }
+
+    fn decrypt_block(&self, block: &mut [u8]) {
+        self.encrypt_block(block)
+    }
+}
@@ -0,0 +1,357 @@
+use std::fmt;
+
+use crate::encodings::{hex_encode_with_case, HexCase};
+
+/// Character separating `key=value` pairs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Separator {
+    /// `key=value&key=value`, as in URL query strings.
+    Ampersand,
+    /// `key=value;key=value`, as in cookies.
+    Semicolon,
+}
+
+impl Separator {
+    fn as_char(self) -> char {
+        match self {
+            Separator::Ampersand => '&',
+            Separator::Semicolon => ';',
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// Percent-encode keys and values, and reject input containing raw metacharacters, bad
+    /// escapes or duplicate keys.
+    Strict,
+    /// Mimic a vulnerable server: write keys and values verbatim, split on the separator and
+    /// the first `=` when parsing, and let later duplicate keys override earlier ones.
+    Naive,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CookieError {
+    /// A pair with no `=`, and its byte offset in the input.
+    MissingEquals {
+        offset: usize,
+    },
+    /// A pair with nothing before the `=`.
+    EmptyKey {
+        offset: usize,
+    },
+    /// A `%` not followed by two hex digits.
+    InvalidEscape {
+        offset: usize,
+    },
+    /// A raw `&`, `;` or `=` inside a key or value.
+    ReservedCharacter {
+        offset: usize,
+        character: char,
+    },
+    /// Percent-decoding produced invalid UTF-8.
+    InvalidUtf8 {
+        offset: usize,
+    },
+    DuplicateKey(String),
+}
+
+impl fmt::Display for CookieError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CookieError::MissingEquals { offset } => write!(f, "missing '=' at offset {}", offset),
+            CookieError::EmptyKey { offset } => write!(f, "empty key at offset {}", offset),
+            CookieError::InvalidEscape { offset } => {
+                write!(f, "invalid percent escape at offset {}", offset)
+            }
+            CookieError::ReservedCharacter { offset, character } => {
+                write!(f, "reserved character {:?} at offset {}", character, offset)
+            }
+            CookieError::InvalidUtf8 { offset } => write!(f, "invalid UTF-8 at offset {}", offset),
+            CookieError::DuplicateKey(key) => write!(f, "duplicate key {:?}", key),
+        }
+    }
+}
+
+impl std::error::Error for CookieError {}
+
+// Characters that strict mode always escapes, whichever separator is in use, so that a cookie
+// can't be reinterpreted by a server that splits on the other one.
+const RESERVED: [char; 3] = ['&', ';', '='];
+
+/// `key=value` pairs in the order they were inserted or parsed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Cookie {
+    pairs: Vec<(String, String)>,
+}
+
+impl Cookie {
+    pub fn new() -> Self {
+        Cookie { pairs: Vec::new() }
+    }
+
+    // Set `key` to `value`, keeping its original position if it's already present.
+    pub fn insert(&mut self, key: &str, value: &str) {
+        match self.pairs.iter_mut().find(|(k, _)| k == key) {
+            Some((_, v)) => *v = value.to_string(),
+            None => self.pairs.push((key.to_string(), value.to_string())),
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.pairs
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn remove(&mut self, key: &str) -> Option<String> {
+        let index = self.pairs.iter().position(|(k, _)| k == key)?;
+        Some(self.pairs.remove(index).1)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.pairs.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.pairs.iter().map(|(k, _)| k.as_str())
+    }
+
+    pub fn len(&self) -> usize {
+        self.pairs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pairs.is_empty()
+    }
+}
+
+impl<K: AsRef<str>, V: AsRef<str>> FromIterator<(K, V)> for Cookie {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut cookie = Cookie::new();
+        for (k, v) in iter {
+            cookie.insert(k.as_ref(), v.as_ref());
+        }
+        cookie
+    }
+}
+
+// Percent-encode everything except the RFC 3986 unreserved characters.
+pub fn percent_encode(s: &str) -> String {
+    let mut encoded = String::with_capacity(s.len());
+    for b in s.bytes() {
+        if b.is_ascii_alphanumeric() || b"-._~".contains(&b) {
+            encoded.push(b as char);
+        } else {
+            encoded.push('%');
+            encoded.push_str(&hex_encode_with_case(&[b], HexCase::Upper));
+        }
+    }
+    encoded
+}
+
+// Percent-decode `s`, which starts at `offset` in the original input, rejecting any `reserved`
+// characters that should have been escaped.
+fn percent_decode_at(s: &str, offset: usize, reserved: &[char]) -> Result<String, CookieError> {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                let escape = bytes
+                    .get(i + 1..i + 3)
+                    .filter(|hex| hex.iter().all(u8::is_ascii_hexdigit))
+                    .and_then(|hex| std::str::from_utf8(hex).ok())
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                    .ok_or(CookieError::InvalidEscape { offset: offset + i })?;
+                decoded.push(escape);
+                i += 3;
+            }
+            b if reserved.contains(&(b as char)) => {
+                return Err(CookieError::ReservedCharacter {
+                    offset: offset + i,
+                    character: b as char,
+                })
+            }
+            b => {
+                decoded.push(b);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(decoded).map_err(|_| CookieError::InvalidUtf8 { offset })
+}
+
+pub fn percent_decode(s: &str) -> Result<String, CookieError> {
+    percent_decode_at(s, 0, &[])
+}
+
+pub fn parse(s: &str, separator: Separator, mode: Mode) -> Result<Cookie, CookieError> {
+    let mut cookie = Cookie::new();
+    let mut offset = 0;
+    for pair in s.split(separator.as_char()) {
+        let pair_offset = offset;
+        offset += pair.len() + 1;
+        if pair.is_empty() {
+            continue;
+        }
+
+        let (key, value) = pair.split_once('=').ok_or(CookieError::MissingEquals {
+            offset: pair_offset,
+        })?;
+        if key.is_empty() {
+            return Err(CookieError::EmptyKey {
+                offset: pair_offset,
+            });
+        }
+
+        match mode {
+            Mode::Naive => cookie.insert(key, value),
+            Mode::Strict => {
+                let value_offset = pair_offset + key.len() + 1;
+                let key = percent_decode_at(key, pair_offset, &RESERVED)?;
+                let value = percent_decode_at(value, value_offset, &RESERVED)?;
+                if cookie.get(&key).is_some() {
+                    return Err(CookieError::DuplicateKey(key));
+                }
+                cookie.insert(&key, &value);
+            }
+        }
+    }
+    Ok(cookie)
+}
+
+pub fn encode(cookie: &Cookie, separator: Separator, mode: Mode) -> String {
+    cookie
+        .iter()
+        .map(|(k, v)| match mode {
+            Mode::Strict => format!("{}={}", percent_encode(k), percent_encode(v)),
+            Mode::Naive => format!("{}={}", k, v),
+        })
+        .collect::<Vec<String>>()
+        .join(&separator.as_char().to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::cookie::{Cookie, CookieError, Mode, Separator};
+
+    #[test]
+    fn parse_keeps_order() {
+        let cookie = crate::cookie::parse(
+            "foo=bar&baz=qux&zap=zazzle",
+            Separator::Ampersand,
+            Mode::Strict,
+        )
+        .unwrap();
+        let keys: Vec<&str> = cookie.keys().collect();
+        assert_eq!(vec!["foo", "baz", "zap"], keys);
+        assert_eq!(Some("zazzle"), cookie.get("zap"));
+    }
+
+    #[test]
+    fn strict_mode_escapes_metacharacters() {
+        let cookie: Cookie = [("email", "foo@bar.com&role=admin"), ("uid", "10")]
+            .into_iter()
+            .collect();
+        let encoded = crate::cookie::encode(&cookie, Separator::Ampersand, Mode::Strict);
+        assert_eq!("email=foo%40bar.com%26role%3Dadmin&uid=10", encoded);
+        let parsed = crate::cookie::parse(&encoded, Separator::Ampersand, Mode::Strict).unwrap();
+        assert_eq!(cookie, parsed);
+        assert_eq!(None, parsed.get("role"));
+    }
+
+    #[test]
+    fn naive_mode_is_injectable() {
+        let cookie: Cookie = [("comment", "x;admin=true"), ("admin", "false")]
+            .into_iter()
+            .collect();
+        let encoded = crate::cookie::encode(&cookie, Separator::Semicolon, Mode::Naive);
+        assert_eq!("comment=x;admin=true;admin=false", encoded);
+
+        let injected = "comment=x;admin=true";
+        let parsed = crate::cookie::parse(injected, Separator::Semicolon, Mode::Naive).unwrap();
+        assert_eq!(Some("true"), parsed.get("admin"));
+
+        let encoded = crate::cookie::encode(&cookie, Separator::Semicolon, Mode::Strict);
+        assert_eq!("comment=x%3Badmin%3Dtrue;admin=false", encoded);
+        let parsed = crate::cookie::parse(&encoded, Separator::Semicolon, Mode::Strict).unwrap();
+        assert_eq!(Some("false"), parsed.get("admin"));
+    }
+
+    #[test]
+    fn naive_mode_last_duplicate_wins() {
+        let parsed =
+            crate::cookie::parse("role=user&role=admin", Separator::Ampersand, Mode::Naive)
+                .unwrap();
+        assert_eq!(Some("admin"), parsed.get("role"));
+        assert_eq!(1, parsed.len());
+    }
+
+    #[test]
+    fn strict_mode_errors() {
+        let parse = |s| crate::cookie::parse(s, Separator::Ampersand, Mode::Strict);
+        assert_eq!(
+            Err(CookieError::MissingEquals { offset: 7 }),
+            parse("a=b&c=&d")
+        );
+        assert_eq!(Err(CookieError::EmptyKey { offset: 4 }), parse("a=b&=c"));
+        assert_eq!(
+            Err(CookieError::InvalidEscape { offset: 3 }),
+            parse("a=b%2")
+        );
+        assert_eq!(
+            Err(CookieError::InvalidEscape { offset: 2 }),
+            parse("a=%+1")
+        );
+        assert_eq!(
+            Err(CookieError::ReservedCharacter {
+                offset: 10,
+                character: '='
+            }),
+            parse("role=admin=true")
+        );
+        assert_eq!(
+            Err(CookieError::DuplicateKey("role".to_string())),
+            parse("role=user&role=admin")
+        );
+    }
+
+    #[test]
+    fn strict_mode_reserves_both_separators() {
+        assert_eq!(
+            Err(CookieError::ReservedCharacter {
+                offset: 3,
+                character: ';'
+            }),
+            crate::cookie::parse("a=b;c", Separator::Ampersand, Mode::Strict)
+        );
+        assert_eq!(
+            Err(CookieError::ReservedCharacter {
+                offset: 3,
+                character: '&'
+            }),
+            crate::cookie::parse("a=b&c", Separator::Semicolon, Mode::Strict)
+        );
+
+        let cookie: Cookie = [("a", "b;c&d")].into_iter().collect();
+        for separator in [Separator::Ampersand, Separator::Semicolon] {
+            let encoded = crate::cookie::encode(&cookie, separator, Mode::Strict);
+            assert_eq!("a=b%3Bc%26d", encoded);
+            assert_eq!(
+                cookie,
+                crate::cookie::parse(&encoded, separator, Mode::Strict).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn percent_round_trip() {
+        let s = "a b&c=d;e%f/é";
+        let encoded = crate::cookie::percent_encode(s);
+        assert_eq!("a%20b%26c%3Dd%3Be%25f%2F%C3%A9", encoded);
+        assert_eq!(s, crate::cookie::percent_decode(&encoded).unwrap());
+    }
+}
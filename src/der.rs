@@ -0,0 +1,582 @@
+use std::fmt;
+use std::fmt::Write;
+
+pub const INTEGER: u8 = 0x02;
+pub const BIT_STRING: u8 = 0x03;
+pub const OCTET_STRING: u8 = 0x04;
+pub const NULL: u8 = 0x05;
+pub const OBJECT_IDENTIFIER: u8 = 0x06;
+pub const SEQUENCE: u8 = 0x30;
+pub const SET: u8 = 0x31;
+
+const CONSTRUCTED: u8 = 0x20;
+
+// Deepest nesting of constructed elements the parser accepts, so that hostile input can't exhaust
+// the stack. Real keys and signatures nest a handful of levels at most.
+pub const MAX_DEPTH: usize = 64;
+
+pub const OID_SHA1: &[u64] = &[1, 3, 14, 3, 2, 26];
+pub const OID_SHA256: &[u64] = &[2, 16, 840, 1, 101, 3, 4, 2, 1];
+pub const OID_RSA_ENCRYPTION: &[u64] = &[1, 2, 840, 113549, 1, 1, 1];
+pub const OID_DSA: &[u64] = &[1, 2, 840, 10040, 4, 1];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DerError {
+    /// The input ends before the element starting at `offset` does.
+    Truncated { offset: usize },
+    /// BER indefinite-length encoding, which DER forbids.
+    IndefiniteLength { offset: usize },
+    /// A length written with more bytes than necessary, which DER forbids.
+    NonMinimalLength { offset: usize },
+    /// A length that doesn't fit in a `usize`.
+    LengthOverflow { offset: usize },
+    /// A multi-byte (high tag number) tag, which this parser doesn't support.
+    UnsupportedTag { offset: usize, tag: u8 },
+    /// Bytes left over after the outermost element.
+    TrailingData { offset: usize },
+    /// Contents that aren't valid for the element's type.
+    InvalidValue { offset: usize, tag: u8 },
+    /// An element nested more than `MAX_DEPTH` levels deep.
+    TooDeep { offset: usize },
+    /// An object identifier that can't be encoded: fewer than two arcs, a first arc above 2, a
+    /// second arc of 40 or more under a first arc of 0 or 1, or arcs too large to combine.
+    InvalidObjectIdentifier(Vec<u64>),
+}
+
+impl fmt::Display for DerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DerError::Truncated { offset } => write!(f, "truncated element at offset {}", offset),
+            DerError::IndefiniteLength { offset } => {
+                write!(f, "indefinite length at offset {}", offset)
+            }
+            DerError::NonMinimalLength { offset } => {
+                write!(f, "non-minimal length at offset {}", offset)
+            }
+            DerError::LengthOverflow { offset } => {
+                write!(f, "length overflow at offset {}", offset)
+            }
+            DerError::UnsupportedTag { offset, tag } => {
+                write!(f, "unsupported tag {:#04x} at offset {}", tag, offset)
+            }
+            DerError::TrailingData { offset } => write!(f, "trailing data at offset {}", offset),
+            DerError::InvalidValue { offset, tag } => {
+                write!(
+                    f,
+                    "invalid contents for tag {:#04x} at offset {}",
+                    tag, offset
+                )
+            }
+            DerError::TooDeep { offset } => {
+                write!(f, "element nested too deeply at offset {}", offset)
+            }
+            DerError::InvalidObjectIdentifier(arcs) => {
+                write!(f, "invalid object identifier {:?}", arcs)
+            }
+        }
+    }
+}
+
+impl std::error::Error for DerError {}
+
+/// A parsed tag-length-value element, with offsets into the original input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Node {
+    pub tag: u8,
+    /// Offset of the tag byte.
+    pub offset: usize,
+    /// Number of tag and length bytes before the contents.
+    pub header_length: usize,
+    pub contents: Vec<u8>,
+    /// Parsed contents of constructed elements such as SEQUENCE and SET.
+    pub children: Vec<Node>,
+}
+
+impl Node {
+    pub fn is_constructed(&self) -> bool {
+        self.tag & CONSTRUCTED != 0
+    }
+
+    // Offset of the first byte after this element.
+    pub fn end(&self) -> usize {
+        self.offset + self.header_length + self.contents.len()
+    }
+
+    pub fn to_value(&self) -> Result<Value, DerError> {
+        let invalid = DerError::InvalidValue {
+            offset: self.offset,
+            tag: self.tag,
+        };
+        Ok(match self.tag {
+            INTEGER if self.contents.is_empty() => return Err(invalid),
+            INTEGER => Value::Integer(self.contents.clone()),
+            BIT_STRING => match self.contents.split_first() {
+                Some((unused_bits, bytes)) if *unused_bits < 8 => Value::BitString {
+                    unused_bits: *unused_bits,
+                    bytes: bytes.to_vec(),
+                },
+                _ => return Err(invalid),
+            },
+            OCTET_STRING => Value::OctetString(self.contents.clone()),
+            NULL if self.contents.is_empty() => Value::Null,
+            NULL => return Err(invalid),
+            OBJECT_IDENTIFIER => {
+                Value::ObjectIdentifier(decode_oid(&self.contents).ok_or(invalid)?)
+            }
+            SEQUENCE => Value::Sequence(children_to_values(&self.children)?),
+            SET => Value::Set(children_to_values(&self.children)?),
+            tag => Value::Other {
+                tag,
+                contents: self.contents.clone(),
+            },
+        })
+    }
+}
+
+fn children_to_values(children: &[Node]) -> Result<Vec<Value>, DerError> {
+    children.iter().map(Node::to_value).collect()
+}
+
+/// An ASN.1 value, for building DER encodings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+    /// Big-endian two's complement contents, as they appear in the encoding.
+    Integer(Vec<u8>),
+    BitString {
+        unused_bits: u8,
+        bytes: Vec<u8>,
+    },
+    OctetString(Vec<u8>),
+    Null,
+    ObjectIdentifier(Vec<u64>),
+    Sequence(Vec<Value>),
+    Set(Vec<Value>),
+    Other {
+        tag: u8,
+        contents: Vec<u8>,
+    },
+}
+
+impl Value {
+    // An INTEGER holding the unsigned big-endian number in `magnitude`, such as an RSA modulus.
+    pub fn unsigned_integer(magnitude: &[u8]) -> Self {
+        let zeros = magnitude.iter().take_while(|b| **b == 0).count();
+        let mut contents = magnitude[zeros..].to_vec();
+        if contents.first().is_none_or(|b| b & 0x80 != 0) {
+            contents.insert(0, 0);
+        }
+        Value::Integer(contents)
+    }
+
+    pub fn from_u64(n: u64) -> Self {
+        Value::unsigned_integer(&n.to_be_bytes())
+    }
+
+    pub fn tag(&self) -> u8 {
+        match self {
+            Value::Integer(_) => INTEGER,
+            Value::BitString { .. } => BIT_STRING,
+            Value::OctetString(_) => OCTET_STRING,
+            Value::Null => NULL,
+            Value::ObjectIdentifier(_) => OBJECT_IDENTIFIER,
+            Value::Sequence(_) => SEQUENCE,
+            Value::Set(_) => SET,
+            Value::Other { tag, .. } => *tag,
+        }
+    }
+}
+
+fn decode_oid(contents: &[u8]) -> Option<Vec<u64>> {
+    let mut arcs = Vec::new();
+    let mut arc: u64 = 0;
+    for (i, b) in contents.iter().enumerate() {
+        if arc == 0 && *b == 0x80 {
+            // Arcs must be minimally encoded.
+            return None;
+        }
+        arc = arc.checked_mul(128)? | (b & 0x7f) as u64;
+        if b & 0x80 == 0 {
+            if arcs.is_empty() {
+                let first = (arc / 40).min(2);
+                arcs.push(first);
+                arcs.push(arc - first * 40);
+            } else {
+                arcs.push(arc);
+            }
+            arc = 0;
+        } else if i == contents.len() - 1 {
+            return None;
+        }
+    }
+    if arcs.is_empty() {
+        return None;
+    }
+    Some(arcs)
+}
+
+fn encode_oid(arcs: &[u64]) -> Result<Vec<u8>, DerError> {
+    let invalid = || DerError::InvalidObjectIdentifier(arcs.to_vec());
+    let (first, second, rest) = match arcs {
+        [first, second, rest @ ..] if *first <= 2 && (*first == 2 || *second < 40) => {
+            (*first, *second, rest)
+        }
+        _ => return Err(invalid()),
+    };
+    let mut values = vec![(first * 40).checked_add(second).ok_or_else(invalid)?];
+    values.extend_from_slice(rest);
+
+    let mut contents = Vec::new();
+    for value in values {
+        let mut groups = vec![(value & 0x7f) as u8];
+        let mut v = value >> 7;
+        while v > 0 {
+            groups.push((v & 0x7f) as u8 | 0x80);
+            v >>= 7;
+        }
+        contents.extend(groups.iter().rev());
+    }
+    Ok(contents)
+}
+
+fn encode_length(len: usize, encoded: &mut Vec<u8>) {
+    if len < 0x80 {
+        encoded.push(len as u8);
+        return;
+    }
+    let bytes = len.to_be_bytes();
+    let zeros = bytes.iter().take_while(|b| **b == 0).count();
+    encoded.push(0x80 | (bytes.len() - zeros) as u8);
+    encoded.extend_from_slice(&bytes[zeros..]);
+}
+
+// Encode `value` as DER. Fails only for object identifiers that DER can't represent.
+pub fn encode(value: &Value) -> Result<Vec<u8>, DerError> {
+    let contents = match value {
+        Value::Integer(contents) | Value::OctetString(contents) => contents.clone(),
+        Value::BitString { unused_bits, bytes } => {
+            let mut contents = vec![*unused_bits];
+            contents.extend_from_slice(bytes);
+            contents
+        }
+        Value::Null => Vec::new(),
+        Value::ObjectIdentifier(arcs) => encode_oid(arcs)?,
+        Value::Sequence(values) | Value::Set(values) => values
+            .iter()
+            .map(encode)
+            .collect::<Result<Vec<_>, _>>()?
+            .concat(),
+        Value::Other { contents, .. } => contents.clone(),
+    };
+    let mut encoded = vec![value.tag()];
+    encode_length(contents.len(), &mut encoded);
+    encoded.extend_from_slice(&contents);
+    Ok(encoded)
+}
+
+// Parse the element at the start of `bytes`, returning it and the number of bytes it used. Any
+// bytes after it are left alone, which mirrors what a lenient verifier does with trailing
+// garbage.
+pub fn parse_prefix(bytes: &[u8]) -> Result<(Node, usize), DerError> {
+    let node = parse_at(bytes, 0, 0)?;
+    let end = node.end();
+    Ok((node, end))
+}
+
+// Parse exactly one element, rejecting trailing data.
+pub fn parse(bytes: &[u8]) -> Result<Node, DerError> {
+    let (node, end) = parse_prefix(bytes)?;
+    if end != bytes.len() {
+        return Err(DerError::TrailingData { offset: end });
+    }
+    Ok(node)
+}
+
+pub fn decode(bytes: &[u8]) -> Result<Value, DerError> {
+    parse(bytes)?.to_value()
+}
+
+fn parse_at(bytes: &[u8], offset: usize, depth: usize) -> Result<Node, DerError> {
+    if depth > MAX_DEPTH {
+        return Err(DerError::TooDeep { offset });
+    }
+    let truncated = DerError::Truncated { offset };
+    let tag = *bytes.get(offset).ok_or(truncated.clone())?;
+    if tag & 0x1f == 0x1f {
+        return Err(DerError::UnsupportedTag { offset, tag });
+    }
+
+    let first = *bytes.get(offset + 1).ok_or(truncated.clone())?;
+    let (len, header_length) = if first < 0x80 {
+        (first as usize, 2)
+    } else if first == 0x80 {
+        return Err(DerError::IndefiniteLength { offset });
+    } else {
+        let count = (first & 0x7f) as usize;
+        let len_bytes = bytes
+            .get(offset + 2..offset + 2 + count)
+            .ok_or(truncated.clone())?;
+        if count > std::mem::size_of::<usize>() {
+            return Err(DerError::LengthOverflow { offset });
+        }
+        let len = len_bytes.iter().fold(0, |acc, b| acc << 8 | *b as usize);
+        if len_bytes[0] == 0 || len < 0x80 {
+            return Err(DerError::NonMinimalLength { offset });
+        }
+        (len, 2 + count)
+    };
+
+    let start = offset + header_length;
+    let end = start.checked_add(len).ok_or(truncated.clone())?;
+    let contents = bytes.get(start..end).ok_or(truncated)?.to_vec();
+
+    let mut children = Vec::new();
+    if tag & CONSTRUCTED != 0 {
+        let mut child_offset = start;
+        while child_offset < end {
+            // Parse within this element's contents so children can't run past its end.
+            let child = parse_at(&bytes[..end], child_offset, depth + 1)?;
+            child_offset = child.end();
+            children.push(child);
+        }
+    }
+
+    Ok(Node {
+        tag,
+        offset,
+        header_length,
+        contents,
+        children,
+    })
+}
+
+fn tag_name(tag: u8) -> String {
+    match tag {
+        INTEGER => "INTEGER".to_string(),
+        BIT_STRING => "BIT STRING".to_string(),
+        OCTET_STRING => "OCTET STRING".to_string(),
+        NULL => "NULL".to_string(),
+        OBJECT_IDENTIFIER => "OBJECT IDENTIFIER".to_string(),
+        SEQUENCE => "SEQUENCE".to_string(),
+        SET => "SET".to_string(),
+        tag => format!("[{:#04x}]", tag),
+    }
+}
+
+// Dump a parsed element as indented text, one element per line prefixed with its offset.
+pub fn pretty_print(node: &Node) -> String {
+    let mut s = String::new();
+    print_node(node, 0, &mut s);
+    s
+}
+
+fn print_node(node: &Node, depth: usize, s: &mut String) {
+    write!(
+        s,
+        "{:04x}: {}{}",
+        node.offset,
+        "  ".repeat(depth),
+        tag_name(node.tag)
+    )
+    .unwrap();
+    match node.tag {
+        OBJECT_IDENTIFIER => match decode_oid(&node.contents) {
+            Some(arcs) => {
+                let arcs: Vec<String> = arcs.iter().map(|a| a.to_string()).collect();
+                write!(s, " {}", arcs.join(".")).unwrap();
+            }
+            None => write!(s, " (invalid)").unwrap(),
+        },
+        NULL => {}
+        _ if node.is_constructed() => write!(s, " ({} bytes)", node.contents.len()).unwrap(),
+        _ => write!(
+            s,
+            " ({} bytes) {}",
+            node.contents.len(),
+            crate::encodings::hex_encode(&node.contents)
+        )
+        .unwrap(),
+    }
+    s.push('\n');
+    for child in &node.children {
+        print_node(child, depth + 1, s);
+    }
+}
+
+// The PKCS #1 DigestInfo structure that RSA signatures wrap around a hash.
+pub fn digest_info(algorithm: &[u64], digest: &[u8]) -> Result<Vec<u8>, DerError> {
+    encode(&Value::Sequence(vec![
+        Value::Sequence(vec![
+            Value::ObjectIdentifier(algorithm.to_vec()),
+            Value::Null,
+        ]),
+        Value::OctetString(digest.to_vec()),
+    ]))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::der::{DerError, Value};
+
+    #[test]
+    fn sha1_digest_info_prefix() {
+        let digest = [0xaa; 20];
+        let encoded = crate::der::digest_info(crate::der::OID_SHA1, &digest).unwrap();
+        assert_eq!(
+            "3021300906052b0e03021a05000414",
+            crate::encodings::hex_encode(&encoded[..15])
+        );
+        assert_eq!(&digest, &encoded[15..]);
+    }
+
+    #[test]
+    fn encode_and_decode_matches_original() {
+        let value = Value::Sequence(vec![
+            Value::from_u64(65537),
+            Value::unsigned_integer(&[0x00, 0x80, 0x01]),
+            Value::BitString {
+                unused_bits: 0,
+                bytes: vec![1, 2, 3],
+            },
+            Value::ObjectIdentifier(crate::der::OID_RSA_ENCRYPTION.to_vec()),
+            Value::Set(vec![Value::OctetString(vec![0; 200])]),
+        ]);
+        let encoded = crate::der::encode(&value).unwrap();
+        assert_eq!(value, crate::der::decode(&encoded).unwrap());
+        assert_eq!(
+            Value::Integer(vec![0x01, 0x00, 0x01]),
+            Value::from_u64(65537)
+        );
+        assert_eq!(
+            Value::Integer(vec![0x00, 0x80, 0x01]),
+            Value::unsigned_integer(&[0x00, 0x80, 0x01])
+        );
+    }
+
+    #[test]
+    fn parse_keeps_offsets() {
+        let encoded = crate::der::digest_info(crate::der::OID_SHA256, &[0; 32]).unwrap();
+        let node = crate::der::parse(&encoded).unwrap();
+        assert_eq!(0, node.offset);
+        assert_eq!(2, node.children[0].offset);
+        assert_eq!(4, node.children[0].children[0].offset);
+        assert_eq!(17, node.children[1].offset);
+        assert_eq!(19, node.children[1].header_length + node.children[1].offset);
+        assert_eq!(encoded.len(), node.end());
+    }
+
+    #[test]
+    fn parse_prefix_reports_where_parsing_stopped() {
+        let mut encoded = crate::der::digest_info(crate::der::OID_SHA1, &[0; 20]).unwrap();
+        let len = encoded.len();
+        encoded.extend_from_slice(&[0xde, 0xad]);
+        let (_, used) = crate::der::parse_prefix(&encoded).unwrap();
+        assert_eq!(len, used);
+        assert_eq!(
+            Err(DerError::TrailingData { offset: len }),
+            crate::der::parse(&encoded)
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(
+            Err(DerError::Truncated { offset: 0 }),
+            crate::der::parse(&[0x04, 0x05, 0x00])
+        );
+        assert_eq!(
+            Err(DerError::IndefiniteLength { offset: 0 }),
+            crate::der::parse(&[0x30, 0x80, 0x00, 0x00])
+        );
+        assert_eq!(
+            Err(DerError::NonMinimalLength { offset: 0 }),
+            crate::der::parse(&[0x04, 0x81, 0x01, 0x00])
+        );
+        // The child claims more bytes than its parent contains.
+        assert_eq!(
+            Err(DerError::Truncated { offset: 2 }),
+            crate::der::parse(&[0x30, 0x03, 0x04, 0x02, 0x00, 0x00])
+        );
+        assert_eq!(
+            Err(DerError::InvalidValue {
+                offset: 0,
+                tag: crate::der::NULL
+            }),
+            crate::der::decode(&[0x05, 0x01, 0x00])
+        );
+    }
+
+    #[test]
+    fn encode_rejects_invalid_object_identifiers() {
+        for arcs in [
+            vec![],
+            vec![1],
+            vec![3, 1],
+            vec![1, 40],
+            vec![u64::MAX, 1],
+            vec![2, u64::MAX - 79],
+        ] {
+            assert_eq!(
+                Err(DerError::InvalidObjectIdentifier(arcs.clone())),
+                crate::der::encode(&Value::ObjectIdentifier(arcs))
+            );
+        }
+
+        let value = Value::ObjectIdentifier(vec![2, 999, 3]);
+        let encoded = crate::der::encode(&value).unwrap();
+        assert_eq!(value, crate::der::decode(&encoded).unwrap());
+        let value = Value::ObjectIdentifier(vec![2, u64::MAX - 80]);
+        let encoded = crate::der::encode(&value).unwrap();
+        assert_eq!(value, crate::der::decode(&encoded).unwrap());
+    }
+
+    #[test]
+    fn parse_rejects_deep_nesting() {
+        let mut value = Value::Null;
+        for _ in 0..crate::der::MAX_DEPTH {
+            value = Value::Sequence(vec![value]);
+        }
+        assert_eq!(
+            value,
+            crate::der::decode(&crate::der::encode(&value).unwrap()).unwrap()
+        );
+
+        // One level deeper, so the innermost NULL is what gets rejected.
+        let encoded = crate::der::encode(&Value::Sequence(vec![value])).unwrap();
+        assert_eq!(
+            Err(DerError::TooDeep {
+                offset: encoded.len() - 2
+            }),
+            crate::der::parse(&encoded)
+        );
+
+        // Far deeper than the stack could handle if parsing recursed all the way down.
+        let mut headers = Vec::new();
+        let mut len = 2;
+        for _ in 0..100_000 {
+            let mut header = vec![crate::der::SEQUENCE];
+            crate::der::encode_length(len, &mut header);
+            len += header.len();
+            headers.push(header);
+        }
+        let mut encoded: Vec<u8> = headers.into_iter().rev().flatten().collect();
+        encoded.extend_from_slice(&[crate::der::NULL, 0x00]);
+        assert!(matches!(
+            crate::der::parse(&encoded),
+            Err(DerError::TooDeep { .. })
+        ));
+    }
+
+    #[test]
+    fn pretty_print_tree() {
+        let encoded = crate::der::digest_info(crate::der::OID_SHA1, &[0xab; 4]).unwrap();
+        let node = crate::der::parse(&encoded).unwrap();
+        assert_eq!(
+            "0000: SEQUENCE (17 bytes)\n\
+             0002:   SEQUENCE (9 bytes)\n\
+             0004:     OBJECT IDENTIFIER 1.3.14.3.2.26\n\
+             000b:     NULL\n\
+             000d:   OCTET STRING (4 bytes) abababab\n",
+            crate::der::pretty_print(&node)
+        );
+    }
+}
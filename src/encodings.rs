@@ -1,139 +1,446 @@
+use std::fmt;
+
+mod ascii85;
+mod base32;
+mod base58;
+mod ct;
+mod detect;
+mod hexdump;
+mod stream;
+
+pub use ascii85::{ascii85_decode, ascii85_encode, z85_decode, z85_encode, Ascii85Error};
+pub use base32::{base32_decode, base32_encode, Base32Error};
+pub use base58::{base58_decode, base58_encode, Base58Error};
+pub use ct::{hex_decode_constant_time, hex_encode_constant_time};
+pub use detect::{detect_and_decode, detect_and_decode_lines, Detected, Encoding};
+pub use hexdump::{hexdump, hexdump_diff, hexdump_with, HexdumpOptions};
+pub use stream::{Base64Decoder, Base64Encoder, HexDecoder, HexEncoder};
+
+/// Letter case used for the digits `a`-`f` when hex encoding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HexCase {
+    Lower,
+    Upper,
+}
+
+/// What a decoder should do when it encounters whitespace in its input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WhitespacePolicy {
+    /// Skip ASCII control characters, such as line breaks and tabs, but not spaces.
+    SkipControl,
+    /// Skip ASCII control characters and any ASCII whitespace, including spaces.
+    SkipAll,
+    /// Treat whitespace like any other character.
+    Reject,
+}
+
+impl WhitespacePolicy {
+    pub(crate) fn skips(self, c: char) -> bool {
+        match self {
+            WhitespacePolicy::SkipControl => c.is_ascii_control(),
+            WhitespacePolicy::SkipAll => c.is_ascii_control() || c.is_ascii_whitespace(),
+            WhitespacePolicy::Reject => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HexError {
+    /// A character that is not a hex digit, and its byte offset in the input.
+    InvalidDigit { offset: usize, character: char },
+    /// The input contained an odd number of hex digits.
+    OddLength(usize),
+}
+
+impl fmt::Display for HexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HexError::InvalidDigit { offset, character } => {
+                write!(f, "invalid hex digit {:?} at offset {}", character, offset)
+            }
+            HexError::OddLength(digits) => write!(f, "odd number of hex digits ({})", digits),
+        }
+    }
+}
+
+impl std::error::Error for HexError {}
+
 pub fn hex_encode(bytes: &[u8]) -> String {
+    hex_encode_with_case(bytes, HexCase::Lower)
+}
+
+pub fn hex_encode_with_case(bytes: &[u8], case: HexCase) -> String {
     bytes
         .iter()
-        .map(|b| format!("{:02x}", b))
+        .map(|b| match case {
+            HexCase::Lower => format!("{:02x}", b),
+            HexCase::Upper => format!("{:02X}", b),
+        })
         .collect::<Vec<String>>()
         .join("")
 }
 
-pub fn hex_decode(hex: &str) -> Vec<u8> {
-    let mut bytes = Vec::new();
-    let mut digits = String::new();
-    for (i, c) in hex.chars().enumerate() {
-        if c.is_ascii_control() {
-            continue;
-        }
-        digits.push(c);
-        if (i == hex.len() - 1) || digits.len() == 2 {
-            let b = u8::from_str_radix(&digits, 16).expect("Invalid hex string");
+// Decode a hex string of either case, skipping ASCII control characters such as line breaks.
+pub fn hex_decode(hex: &str) -> Result<Vec<u8>, HexError> {
+    hex_decode_with(hex, WhitespacePolicy::SkipControl)
+}
+
+pub fn hex_decode_with(hex: &str, whitespace: WhitespacePolicy) -> Result<Vec<u8>, HexError> {
+    let mut bytes = Vec::with_capacity(hex.len() / 2);
+    let mut state = HexDecodeState::new(whitespace);
+    for (offset, c) in hex.char_indices() {
+        if let Some(b) = state.push(offset, c)? {
             bytes.push(b);
-            digits = String::new();
         }
     }
-    bytes
+    state.finish()?;
+    Ok(bytes)
 }
 
-const ALPHABET: [char; 64] = [
-    'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'I', 'J', 'K', 'L', 'M', 'N', 'O', 'P', 'Q', 'R', 'S',
-    'T', 'U', 'V', 'W', 'X', 'Y', 'Z', 'a', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'i', 'j', 'k', 'l',
-    'm', 'n', 'o', 'p', 'q', 'r', 's', 't', 'u', 'v', 'w', 'x', 'y', 'z', '0', '1', '2', '3', '4',
-    '5', '6', '7', '8', '9', '+', '/',
-];
+// Incremental hex decoding, shared by `hex_decode_with` and the streaming decoder.
+pub(crate) struct HexDecodeState {
+    whitespace: WhitespacePolicy,
+    high: Option<u8>,
+    digits: usize,
+}
 
-pub fn base64_encode(bytes: &[u8]) -> String {
-    let mut s = String::new();
-    let mut c = [0; 3];
-    let mut i: usize = 0;
-    let mut u: u64;
-    let mut read;
-
-    while i < bytes.len() {
-        c[1] = 0;
-        c[2] = 0;
-
-        c[0] = bytes[i];
-        i += 1;
-        read = 1;
-
-        if i < bytes.len() {
-            c[1] = bytes[i];
-            i += 1;
-            read = 2;
+impl HexDecodeState {
+    pub(crate) fn new(whitespace: WhitespacePolicy) -> Self {
+        HexDecodeState {
+            whitespace,
+            high: None,
+            digits: 0,
+        }
+    }
 
-            if i < bytes.len() {
-                c[2] = bytes[i];
-                i += 1;
-                read = 3;
+    pub(crate) fn push(&mut self, offset: usize, c: char) -> Result<Option<u8>, HexError> {
+        if self.whitespace.skips(c) {
+            return Ok(None);
+        }
+        let nibble = c.to_digit(16).ok_or(HexError::InvalidDigit {
+            offset,
+            character: c,
+        })? as u8;
+        self.digits += 1;
+        Ok(match self.high.take() {
+            Some(h) => Some(h << 4 | nibble),
+            None => {
+                self.high = Some(nibble);
+                None
             }
+        })
+    }
+
+    pub(crate) fn finish(self) -> Result<(), HexError> {
+        match self.high {
+            Some(_) => Err(HexError::OddLength(self.digits)),
+            None => Ok(()),
         }
+    }
+}
 
-        u = (c[0] as u64) << 16;
-        u |= (c[1] as u64) << 8;
-        u |= c[2] as u64;
+pub const STANDARD_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+pub const URL_SAFE_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
 
-        s.push(ALPHABET[(u >> 18) as usize]);
-        s.push(ALPHABET[((u >> 12) & 63) as usize]);
-        s.push(if read < 2 {
-            '='
-        } else {
-            ALPHABET[(u >> 6 & 63) as usize]
-        });
-        s.push(if read < 3 {
-            '='
-        } else {
-            ALPHABET[(u & 63) as usize]
-        });
-    }
+const INVALID: u8 = 0xFF;
 
-    s
+/// How a Base64 engine treats `=` padding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Base64Padding {
+    /// Pad when encoding and reject unpadded input when decoding.
+    Required,
+    /// Pad when encoding, but accept input with or without padding.
+    Optional,
+    /// Never pad when encoding and reject padded input when decoding.
+    Forbidden,
 }
 
-fn find_index(c: char) -> Result<u8, &'static str> {
-    let ascii = c as u8;
-    match c {
-        'A'..='Z' => Ok(ascii - ('A' as u8)),
-        'a'..='z' => Ok(ascii - ('a' as u8) + 26),
-        '0'..='9' => Ok(ascii - ('0' as u8) + 52),
-        '+' => Ok(62),
-        '/' => Ok(63),
-        _ => Err("Invalid Base64 character"),
-    }
+/// Break encoded output into lines of `width` characters separated by `ending`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineWrap {
+    pub width: usize,
+    pub ending: &'static str,
 }
 
-pub fn base64_decode(s: &str) -> Result<Vec<u8>, &'static str> {
-    let chars = s
-        .chars()
-        .filter(|c| !c.is_ascii_control())
-        .collect::<Vec<char>>();
-    if (chars.len() % 4) != 0 {
-        return Err("Invalid Base64 string length");
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Base64Error {
+    /// A character outside the alphabet, and its byte offset in the input.
+    InvalidCharacter { offset: usize, character: char },
+    /// The number of Base64 characters cannot encode a whole number of bytes.
+    InvalidLength(usize),
+    /// Padding that is missing, misplaced or not allowed by the engine.
+    InvalidPadding,
+    /// The final character has non-zero unused bits, so the encoding is not canonical.
+    NonCanonical { offset: usize },
+}
+
+impl fmt::Display for Base64Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Base64Error::InvalidCharacter { offset, character } => {
+                write!(
+                    f,
+                    "invalid Base64 character {:?} at offset {}",
+                    character, offset
+                )
+            }
+            Base64Error::InvalidLength(len) => write!(f, "invalid Base64 string length ({})", len),
+            Base64Error::InvalidPadding => write!(f, "invalid Base64 padding"),
+            Base64Error::NonCanonical { offset } => {
+                write!(f, "non-zero trailing bits at offset {}", offset)
+            }
+        }
     }
+}
+
+impl std::error::Error for Base64Error {}
+
+/// A Base64 encoder/decoder with a configurable alphabet, padding, line wrapping and strictness.
+#[derive(Debug, Clone, Copy)]
+pub struct Base64Engine {
+    alphabet: [u8; 64],
+    decode_table: [u8; 256],
+    padding: Base64Padding,
+    wrap: Option<LineWrap>,
+    whitespace: WhitespacePolicy,
+    strict: bool,
+}
+
+/// RFC 4648 standard alphabet with mandatory padding, as used by `base64_encode`/`base64_decode`.
+pub const STANDARD: Base64Engine = Base64Engine::new(STANDARD_ALPHABET);
+/// RFC 4648 URL and filename safe alphabet with padding.
+pub const URL_SAFE: Base64Engine = Base64Engine::new(URL_SAFE_ALPHABET);
+/// RFC 4648 URL and filename safe alphabet without padding, as used in cookies and tokens.
+pub const URL_SAFE_NO_PAD: Base64Engine =
+    Base64Engine::new(URL_SAFE_ALPHABET).with_padding(Base64Padding::Forbidden);
+/// RFC 2045 MIME encoding: standard alphabet wrapped at 76 columns with CRLF line endings.
+pub const MIME: Base64Engine = Base64Engine::new(STANDARD_ALPHABET)
+    .with_line_wrap(76, "\r\n")
+    .with_whitespace(WhitespacePolicy::SkipAll);
 
-    let mut acc: u8;
-    let mut decoded = Vec::new();
+impl Base64Engine {
+    pub const fn new(alphabet: &[u8; 64]) -> Self {
+        let mut decode_table = [INVALID; 256];
         let mut i = 0;
-    while i < chars.len() {
-        let b1 = chars[i];
-        let b2 = chars[i + 1];
-        let b3 = chars[i + 2];
-        let b4 = chars[i + 3];
-        i += 4;
+        while i < alphabet.len() {
+            decode_table[alphabet[i] as usize] = i as u8;
+            i += 1;
+        }
+        Base64Engine {
+            alphabet: *alphabet,
+            decode_table,
+            padding: Base64Padding::Required,
+            wrap: None,
+            whitespace: WhitespacePolicy::SkipControl,
+            strict: false,
+        }
+    }
+
+    pub const fn with_padding(mut self, padding: Base64Padding) -> Self {
+        self.padding = padding;
+        self
+    }
 
-        let i1 = find_index(b1)?;
-        let i2 = find_index(b2)?;
+    pub const fn with_line_wrap(mut self, width: usize, ending: &'static str) -> Self {
+        self.wrap = Some(LineWrap { width, ending });
+        self
+    }
+
+    pub const fn with_whitespace(mut self, whitespace: WhitespacePolicy) -> Self {
+        self.whitespace = whitespace;
+        self
+    }
 
-        acc = i1 << 2;
-        acc |= i2 >> 4;
-        decoded.push(acc);
+    // In strict mode, reject encodings whose final character has non-zero unused bits, which
+    // would otherwise let several strings decode to the same bytes.
+    pub const fn with_strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
 
-        if b3 != '=' {
-            let i3 = find_index(b3)?;
+    pub fn padding(&self) -> Base64Padding {
+        self.padding
+    }
 
-            acc = (i2 & 0xF) << 4;
-            acc += i3 >> 2; // Should this be an &=?
-            decoded.push(acc);
+    pub fn line_wrap(&self) -> Option<LineWrap> {
+        self.wrap
+    }
+
+    pub fn encode(&self, bytes: &[u8]) -> String {
+        let mut s = String::with_capacity(bytes.len().div_ceil(3) * 4);
+        for chunk in bytes.chunks(3) {
+            self.encode_quantum(chunk, &mut s);
+        }
+
+        match self.wrap {
+            Some(wrap) => wrap_lines(&s, wrap),
+            None => s,
+        }
+    }
 
-            if b4 != '=' {
-                let i4 = find_index(b4)?;
+    // Encode up to three bytes as one four-character quantum, padding it if the engine allows.
+    pub(crate) fn encode_quantum(&self, chunk: &[u8], s: &mut String) {
+        let mut u = (chunk[0] as u32) << 16;
+        if let Some(b) = chunk.get(1) {
+            u |= (*b as u32) << 8;
+        }
+        if let Some(b) = chunk.get(2) {
+            u |= *b as u32;
+        }
 
-                acc = (i3 & 0x3) << 6;
-                acc |= i4;
-                decoded.push(acc);
+        let symbols = chunk.len() + 1;
+        for i in 0..4 {
+            if i < symbols {
+                let index = (u >> (18 - 6 * i)) & 63;
+                s.push(self.alphabet[index as usize] as char);
+            } else if self.padding != Base64Padding::Forbidden {
+                s.push('=');
             }
         }
     }
 
-    Ok(decoded)
+    pub fn decode(&self, s: &str) -> Result<Vec<u8>, Base64Error> {
+        let mut decoded = Vec::with_capacity(s.len() / 4 * 3 + 2);
+        let mut state = Base64DecodeState::new(self);
+        for (offset, c) in s.char_indices() {
+            state.push(offset, c, &mut decoded)?;
+        }
+        state.finish(&mut decoded)?;
+        Ok(decoded)
+    }
+}
+
+// Incremental Base64 decoding, shared by `Base64Engine::decode` and the streaming decoder so that
+// quanta can be split across input chunks.
+pub(crate) struct Base64DecodeState {
+    engine: Base64Engine,
+    quantum: [(usize, u8); 4],
+    len: usize,
+    symbols: usize,
+    padding: usize,
+}
+
+impl Base64DecodeState {
+    pub(crate) fn new(engine: &Base64Engine) -> Self {
+        Base64DecodeState {
+            engine: *engine,
+            quantum: [(0, 0); 4],
+            len: 0,
+            symbols: 0,
+            padding: 0,
+        }
+    }
+
+    pub(crate) fn push(
+        &mut self,
+        offset: usize,
+        c: char,
+        decoded: &mut Vec<u8>,
+    ) -> Result<(), Base64Error> {
+        if self.engine.whitespace.skips(c) {
+            return Ok(());
+        }
+        if c == '=' {
+            self.padding += 1;
+            return Ok(());
+        }
+        if self.padding > 0 {
+            // Data after padding.
+            return Err(Base64Error::InvalidPadding);
+        }
+        let index = if c.is_ascii() {
+            self.engine.decode_table[c as usize]
+        } else {
+            INVALID
+        };
+        if index == INVALID {
+            return Err(Base64Error::InvalidCharacter {
+                offset,
+                character: c,
+            });
+        }
+
+        self.quantum[self.len] = (offset, index);
+        self.len += 1;
+        self.symbols += 1;
+        if self.len == 4 {
+            self.flush(decoded)?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn finish(mut self, decoded: &mut Vec<u8>) -> Result<(), Base64Error> {
+        if self.len == 1 {
+            return Err(Base64Error::InvalidLength(self.symbols + self.padding));
+        }
+        let expected_padding = (4 - self.len) % 4;
+        match self.engine.padding {
+            Base64Padding::Required if self.padding != expected_padding => {
+                return Err(if self.padding == 0 {
+                    Base64Error::InvalidLength(self.symbols)
+                } else {
+                    Base64Error::InvalidPadding
+                });
+            }
+            Base64Padding::Optional if self.padding != 0 && self.padding != expected_padding => {
+                return Err(Base64Error::InvalidPadding);
+            }
+            Base64Padding::Forbidden if self.padding != 0 => {
+                return Err(Base64Error::InvalidPadding)
+            }
+            _ => {}
+        }
+
+        if self.len > 0 {
+            self.flush(decoded)?;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self, decoded: &mut Vec<u8>) -> Result<(), Base64Error> {
+        let group = &self.quantum[..self.len];
+        let mut u: u32 = 0;
+        for (i, (_, index)) in group.iter().enumerate() {
+            u |= (*index as u32) << (18 - 6 * i);
+        }
+        let bytes = [(u >> 16) as u8, (u >> 8) as u8, u as u8];
+        let len = group.len() - 1;
+        decoded.extend_from_slice(&bytes[..len]);
+
+        if self.engine.strict && len < 3 {
+            // Any bits below the last whole byte should be zero.
+            let unused = u & ((1 << (24 - 8 * len)) - 1);
+            if unused != 0 {
+                let (offset, _) = group[group.len() - 1];
+                return Err(Base64Error::NonCanonical { offset });
+            }
+        }
+
+        self.len = 0;
+        Ok(())
+    }
+}
+
+fn wrap_lines(s: &str, wrap: LineWrap) -> String {
+    if wrap.width == 0 {
+        return s.to_string();
+    }
+    // Encoded output is always ASCII, so chunking the bytes never splits a character.
+    s.as_bytes()
+        .chunks(wrap.width)
+        .map(|line| std::str::from_utf8(line).unwrap())
+        .collect::<Vec<&str>>()
+        .join(wrap.ending)
+}
+
+pub fn base64_encode(bytes: &[u8]) -> String {
+    STANDARD.encode(bytes)
+}
+
+pub fn base64_decode(s: &str) -> Result<Vec<u8>, Base64Error> {
+    STANDARD.decode(s)
 }
 
 mod test {
@@ -151,7 +458,7 @@ mod test {
     #[test]
     fn decode_hex_string() {
         let hex = "49276d206b696c6c696e6720796f757220627261696e206c696b65206120706f69736f6e6f7573206d757368726f6f6d";
-        let decoded_bytes = crate::encodings::hex_decode(hex);
+        let decoded_bytes = crate::encodings::hex_decode(hex).unwrap();
         let decoded_string = std::str::from_utf8(&decoded_bytes).unwrap();
         assert_eq!(
             decoded_string,
@@ -162,7 +469,7 @@ mod test {
     #[test]
     fn decode_hex_string_with_newline() {
         let hex = "49276d206b696c6c696e6720796f757220627261696e206c696b65206120706f69736f6e6f757320\n6d757368726f6f6d";
-        let decoded_bytes = crate::encodings::hex_decode(hex);
+        let decoded_bytes = crate::encodings::hex_decode(hex).unwrap();
         let decoded_string = std::str::from_utf8(&decoded_bytes).unwrap();
         assert_eq!(
             decoded_string,
@@ -170,6 +477,67 @@ mod test {
         );
     }
 
+    #[test]
+    fn hex_encode_upper_case() {
+        let encoded = crate::encodings::hex_encode_with_case(
+            &[0xde, 0xad, 0xbe, 0xef],
+            super::HexCase::Upper,
+        );
+        assert_eq!("DEADBEEF", encoded);
+    }
+
+    #[test]
+    fn decode_upper_case_hex_string() {
+        assert_eq!(
+            vec![0xde, 0xad, 0xbe, 0xef],
+            crate::encodings::hex_decode("DEADbeef").unwrap()
+        );
+    }
+
+    #[test]
+    fn decode_hex_string_with_invalid_digit() {
+        assert_eq!(
+            Err(super::HexError::InvalidDigit {
+                offset: 3,
+                character: 'g'
+            }),
+            crate::encodings::hex_decode("\n12g4")
+        );
+    }
+
+    #[test]
+    fn decode_odd_length_hex_string() {
+        assert_eq!(
+            Err(super::HexError::OddLength(3)),
+            crate::encodings::hex_decode("123")
+        );
+    }
+
+    #[test]
+    fn decode_hex_string_whitespace_policy() {
+        use super::WhitespacePolicy;
+
+        let hex = "de ad\nbe ef";
+        assert_eq!(
+            vec![0xde, 0xad, 0xbe, 0xef],
+            crate::encodings::hex_decode_with(hex, WhitespacePolicy::SkipAll).unwrap()
+        );
+        assert_eq!(
+            Err(super::HexError::InvalidDigit {
+                offset: 2,
+                character: ' '
+            }),
+            crate::encodings::hex_decode_with(hex, WhitespacePolicy::SkipControl)
+        );
+        assert_eq!(
+            Err(super::HexError::InvalidDigit {
+                offset: 4,
+                character: '\n'
+            }),
+            crate::encodings::hex_decode_with("dead\nbeef", WhitespacePolicy::Reject)
+        );
+    }
+
     #[test]
     fn base64_encode_string() {
         let encoded = crate::encodings::base64_encode("My hovercraft is full of eels.".as_bytes());
@@ -205,4 +573,81 @@ mod test {
         let decoded = crate::encodings::base64_decode(&encoded).unwrap();
         assert_eq!(bytes, decoded.as_slice());
     }
+
+    #[test]
+    fn url_safe_no_pad_round_trip() {
+        let bytes = [0xfb, 0xff, 0xbf, 0x3e];
+        let encoded = crate::encodings::URL_SAFE_NO_PAD.encode(&bytes);
+        assert_eq!("-_-_Pg", encoded);
+        assert_eq!(
+            bytes.to_vec(),
+            crate::encodings::URL_SAFE_NO_PAD.decode(&encoded).unwrap()
+        );
+        assert_eq!("-_-_Pg==", crate::encodings::URL_SAFE.encode(&bytes));
+    }
+
+    #[test]
+    fn decode_base64_padding_policies() {
+        use super::{Base64Error, Base64Padding, STANDARD, URL_SAFE_NO_PAD};
+
+        assert_eq!(
+            Err(Base64Error::InvalidLength(15)),
+            STANDARD.decode("bGlnaHQgd29yay4")
+        );
+        assert_eq!(
+            Err(Base64Error::InvalidPadding),
+            URL_SAFE_NO_PAD.decode("bGlnaHQgd29yay4=")
+        );
+        assert_eq!(
+            Err(Base64Error::InvalidPadding),
+            STANDARD.decode("bGlnaHQgd29yay4==")
+        );
+
+        let optional = STANDARD.with_padding(Base64Padding::Optional);
+        assert_eq!(
+            b"light work.".to_vec(),
+            optional.decode("bGlnaHQgd29yay4").unwrap()
+        );
+        assert_eq!(
+            b"light work.".to_vec(),
+            optional.decode("bGlnaHQgd29yay4=").unwrap()
+        );
+    }
+
+    #[test]
+    fn decode_base64_invalid_character() {
+        assert_eq!(
+            Err(super::Base64Error::InvalidCharacter {
+                offset: 2,
+                character: '-'
+            }),
+            crate::encodings::base64_decode("ab-d")
+        );
+    }
+
+    #[test]
+    fn decode_non_canonical_base64() {
+        // "QR==" has unused bits set; the canonical encoding of the same byte is "QQ==".
+        assert_eq!(
+            b"A".to_vec(),
+            crate::encodings::base64_decode("QR==").unwrap()
+        );
+        assert_eq!(
+            Err(super::Base64Error::NonCanonical { offset: 1 }),
+            crate::encodings::STANDARD.with_strict(true).decode("QR==")
+        );
+    }
+
+    #[test]
+    fn mime_wraps_at_76_columns() {
+        let bytes = [0x55; 100];
+        let encoded = crate::encodings::MIME.encode(&bytes);
+        let lines: Vec<&str> = encoded.split("\r\n").collect();
+        assert_eq!(2, lines.len());
+        assert_eq!(76, lines[0].len());
+        assert_eq!(
+            bytes.to_vec(),
+            crate::encodings::MIME.decode(&encoded).unwrap()
+        );
+    }
 }
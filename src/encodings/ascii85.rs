@@ -0,0 +1,242 @@
+use std::fmt;
+
+use super::WhitespacePolicy;
+
+const Z85_ALPHABET: &[u8; 85] =
+    b"0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ.-:+=^!/*?&<>()[]{}@%$#";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Ascii85Error {
+    /// A character outside the alphabet, and its byte offset in the input.
+    InvalidCharacter { offset: usize, character: char },
+    /// The input length is not valid for the encoding.
+    InvalidLength(usize),
+    /// A group whose value does not fit in four bytes, and the offset of its last character.
+    Overflow { offset: usize },
+}
+
+impl fmt::Display for Ascii85Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Ascii85Error::InvalidCharacter { offset, character } => {
+                write!(
+                    f,
+                    "invalid Ascii85 character {:?} at offset {}",
+                    character, offset
+                )
+            }
+            Ascii85Error::InvalidLength(len) => write!(f, "invalid Ascii85 length ({})", len),
+            Ascii85Error::Overflow { offset } => {
+                write!(f, "Ascii85 group overflows at offset {}", offset)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Ascii85Error {}
+
+fn encode_group(chunk: &[u8], alphabet: impl Fn(u32) -> char, s: &mut String) {
+    let mut u: u32 = 0;
+    for (i, b) in chunk.iter().enumerate() {
+        u |= (*b as u32) << (24 - 8 * i);
+    }
+    let mut digits = [0; 5];
+    for digit in digits.iter_mut().rev() {
+        *digit = u % 85;
+        u /= 85;
+    }
+    // A partial group of n bytes is written as its first n + 1 characters.
+    for digit in &digits[..chunk.len() + 1] {
+        s.push(alphabet(*digit));
+    }
+}
+
+// Decode a group of up to five digits, padding a partial group with the highest digit.
+fn decode_group(group: &[(usize, u32)], decoded: &mut Vec<u8>) -> Result<(), Ascii85Error> {
+    let mut u: u64 = 0;
+    for i in 0..5 {
+        u = u * 85 + group.get(i).map_or(84, |(_, digit)| *digit) as u64;
+    }
+    if u > u32::MAX as u64 {
+        let (offset, _) = group[group.len() - 1];
+        return Err(Ascii85Error::Overflow { offset });
+    }
+    decoded.extend_from_slice(&(u as u32).to_be_bytes()[..group.len() - 1]);
+    Ok(())
+}
+
+// Encode using Adobe Ascii85, without the `<~`/`~>` delimiters. Groups of four zero bytes are
+// abbreviated to `z`.
+pub fn ascii85_encode(bytes: &[u8]) -> String {
+    let mut s = String::with_capacity(bytes.len().div_ceil(4) * 5);
+    for chunk in bytes.chunks(4) {
+        if chunk == [0, 0, 0, 0] {
+            s.push('z');
+        } else {
+            encode_group(chunk, |d| (b'!' + d as u8) as char, &mut s);
+        }
+    }
+    s
+}
+
+// Decode Adobe Ascii85. The `<~`/`~>` delimiters are optional and, as in PDF streams, all
+// whitespace is ignored.
+pub fn ascii85_decode(s: &str) -> Result<Vec<u8>, Ascii85Error> {
+    let trimmed = s.trim();
+    let start = if trimmed.starts_with("<~") { 2 } else { 0 };
+    let end = trimmed.len() - if trimmed.ends_with("~>") { 2 } else { 0 };
+    let base = s.len() - s.trim_start().len();
+
+    let mut decoded = Vec::with_capacity(s.len() * 4 / 5);
+    let mut group: Vec<(usize, u32)> = Vec::with_capacity(5);
+    let mut symbols = 0;
+    for (i, c) in trimmed[start..end.max(start)].char_indices() {
+        let offset = base + start + i;
+        if WhitespacePolicy::SkipAll.skips(c) {
+            continue;
+        }
+        symbols += 1;
+        match c {
+            'z' if group.is_empty() => decoded.extend_from_slice(&[0; 4]),
+            '!'..='u' => {
+                group.push((offset, c as u32 - '!' as u32));
+                if group.len() == 5 {
+                    decode_group(&group, &mut decoded)?;
+                    group.clear();
+                }
+            }
+            _ => {
+                return Err(Ascii85Error::InvalidCharacter {
+                    offset,
+                    character: c,
+                })
+            }
+        }
+    }
+
+    match group.len() {
+        0 => {}
+        1 => return Err(Ascii85Error::InvalidLength(symbols)),
+        _ => decode_group(&group, &mut decoded)?,
+    }
+    Ok(decoded)
+}
+
+// Encode using ZeroMQ's Z85, which is only defined for inputs that are a multiple of four bytes.
+pub fn z85_encode(bytes: &[u8]) -> Result<String, Ascii85Error> {
+    if !bytes.len().is_multiple_of(4) {
+        return Err(Ascii85Error::InvalidLength(bytes.len()));
+    }
+    let mut s = String::with_capacity(bytes.len() / 4 * 5);
+    for chunk in bytes.chunks(4) {
+        encode_group(chunk, |d| Z85_ALPHABET[d as usize] as char, &mut s);
+    }
+    Ok(s)
+}
+
+pub fn z85_decode(s: &str) -> Result<Vec<u8>, Ascii85Error> {
+    let mut decoded = Vec::with_capacity(s.len() * 4 / 5);
+    let mut group: Vec<(usize, u32)> = Vec::with_capacity(5);
+    let mut symbols = 0;
+    for (offset, c) in s.char_indices() {
+        if WhitespacePolicy::SkipControl.skips(c) {
+            continue;
+        }
+        symbols += 1;
+        let digit = if c.is_ascii() {
+            Z85_ALPHABET.iter().position(|a| *a == c as u8)
+        } else {
+            None
+        };
+        match digit {
+            Some(digit) => group.push((offset, digit as u32)),
+            None => {
+                return Err(Ascii85Error::InvalidCharacter {
+                    offset,
+                    character: c,
+                })
+            }
+        }
+        if group.len() == 5 {
+            decode_group(&group, &mut decoded)?;
+            group.clear();
+        }
+    }
+
+    if !group.is_empty() {
+        return Err(Ascii85Error::InvalidLength(symbols));
+    }
+    Ok(decoded)
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn ascii85_encode_string() {
+        let encoded = crate::encodings::ascii85_encode("Man is distinguished".as_bytes());
+        assert_eq!("9jqo^BlbD-BleB1DJ+*+F(f,q", encoded);
+    }
+
+    #[test]
+    fn ascii85_zero_group_and_partial_group() {
+        let bytes = [0, 0, 0, 0, 1, 2];
+        let encoded = crate::encodings::ascii85_encode(&bytes);
+        assert!(encoded.starts_with('z'));
+        assert_eq!(
+            bytes.to_vec(),
+            crate::encodings::ascii85_decode(&encoded).unwrap()
+        );
+    }
+
+    #[test]
+    fn ascii85_decode_with_delimiters_and_whitespace() {
+        let decoded = crate::encodings::ascii85_decode("<~9jqo^BlbD-\n BleB1DJ+*+F(f,q~>").unwrap();
+        assert_eq!("Man is distinguished".as_bytes(), decoded);
+    }
+
+    #[test]
+    fn ascii85_decode_errors() {
+        use crate::encodings::Ascii85Error;
+
+        assert_eq!(
+            Err(Ascii85Error::InvalidLength(6)),
+            crate::encodings::ascii85_decode("9jqo^B")
+        );
+        assert_eq!(
+            Err(Ascii85Error::Overflow { offset: 4 }),
+            crate::encodings::ascii85_decode("uuuuu")
+        );
+        assert_eq!(
+            Err(Ascii85Error::InvalidCharacter {
+                offset: 2,
+                character: 'z'
+            }),
+            crate::encodings::ascii85_decode("9jz")
+        );
+    }
+
+    #[test]
+    fn z85_test_vector() {
+        let bytes = [0x86, 0x4F, 0xD2, 0x6F, 0xB5, 0x59, 0xF7, 0x5B];
+        let encoded = crate::encodings::z85_encode(&bytes).unwrap();
+        assert_eq!("HelloWorld", encoded);
+        assert_eq!(
+            bytes.to_vec(),
+            crate::encodings::z85_decode(&encoded).unwrap()
+        );
+    }
+
+    #[test]
+    fn z85_rejects_partial_groups() {
+        use crate::encodings::Ascii85Error;
+
+        assert_eq!(
+            Err(Ascii85Error::InvalidLength(3)),
+            crate::encodings::z85_encode(&[1, 2, 3])
+        );
+        assert_eq!(
+            Err(Ascii85Error::InvalidLength(4)),
+            crate::encodings::z85_decode("Hell")
+        );
+    }
+}
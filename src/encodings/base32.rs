@@ -0,0 +1,165 @@
+use std::fmt;
+
+use super::WhitespacePolicy;
+
+const ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+// Number of Base32 characters needed for a group of 0 to 5 bytes.
+const SYMBOLS_FOR_BYTES: [usize; 6] = [0, 2, 4, 5, 7, 8];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Base32Error {
+    /// A character outside the alphabet, and its byte offset in the input.
+    InvalidCharacter { offset: usize, character: char },
+    /// The number of Base32 characters cannot encode a whole number of bytes.
+    InvalidLength(usize),
+    /// Padding that is misplaced or of the wrong length.
+    InvalidPadding,
+}
+
+impl fmt::Display for Base32Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Base32Error::InvalidCharacter { offset, character } => {
+                write!(
+                    f,
+                    "invalid Base32 character {:?} at offset {}",
+                    character, offset
+                )
+            }
+            Base32Error::InvalidLength(len) => write!(f, "invalid Base32 string length ({})", len),
+            Base32Error::InvalidPadding => write!(f, "invalid Base32 padding"),
+        }
+    }
+}
+
+impl std::error::Error for Base32Error {}
+
+// Encode using the RFC 4648 Base32 alphabet, padding the output to a multiple of eight characters.
+pub fn base32_encode(bytes: &[u8]) -> String {
+    let mut s = String::with_capacity(bytes.len().div_ceil(5) * 8);
+    for chunk in bytes.chunks(5) {
+        let mut u: u64 = 0;
+        for (i, b) in chunk.iter().enumerate() {
+            u |= (*b as u64) << (32 - 8 * i);
+        }
+        let symbols = SYMBOLS_FOR_BYTES[chunk.len()];
+        for i in 0..8 {
+            if i < symbols {
+                s.push(ALPHABET[((u >> (35 - 5 * i)) & 31) as usize] as char);
+            } else {
+                s.push('=');
+            }
+        }
+    }
+    s
+}
+
+// Decode RFC 4648 Base32. Letters may be in either case and padding may be omitted, as is common
+// for TOTP secrets, but padding that is present must be correct.
+pub fn base32_decode(s: &str) -> Result<Vec<u8>, Base32Error> {
+    let mut symbols: Vec<u8> = Vec::with_capacity(s.len());
+    let mut padding = 0;
+    for (offset, c) in s.char_indices() {
+        if WhitespacePolicy::SkipControl.skips(c) {
+            continue;
+        }
+        if c == '=' {
+            padding += 1;
+            continue;
+        }
+        if padding > 0 {
+            return Err(Base32Error::InvalidPadding);
+        }
+        let upper = c.to_ascii_uppercase();
+        let index = match upper {
+            'A'..='Z' => upper as u8 - b'A',
+            '2'..='7' => upper as u8 - b'2' + 26,
+            _ => {
+                return Err(Base32Error::InvalidCharacter {
+                    offset,
+                    character: c,
+                })
+            }
+        };
+        symbols.push(index);
+    }
+
+    let remainder = symbols.len() % 8;
+    let bytes_in_last = match SYMBOLS_FOR_BYTES.iter().position(|n| *n == remainder) {
+        Some(n) => n,
+        None => return Err(Base32Error::InvalidLength(symbols.len() + padding)),
+    };
+    if padding != 0 && padding != (8 - remainder) % 8 {
+        return Err(Base32Error::InvalidPadding);
+    }
+
+    let mut decoded = Vec::with_capacity(symbols.len() * 5 / 8);
+    for group in symbols.chunks(8) {
+        let mut u: u64 = 0;
+        for (i, index) in group.iter().enumerate() {
+            u |= (*index as u64) << (35 - 5 * i);
+        }
+        let len = if group.len() == 8 { 5 } else { bytes_in_last };
+        for i in 0..len {
+            decoded.push((u >> (32 - 8 * i)) as u8);
+        }
+    }
+    Ok(decoded)
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn base32_rfc4648_vectors() {
+        let vectors = [
+            ("", ""),
+            ("f", "MY======"),
+            ("fo", "MZXQ===="),
+            ("foo", "MZXW6==="),
+            ("foob", "MZXW6YQ="),
+            ("fooba", "MZXW6YTB"),
+            ("foobar", "MZXW6YTBOI======"),
+        ];
+        for (plain, encoded) in vectors {
+            assert_eq!(encoded, crate::encodings::base32_encode(plain.as_bytes()));
+            assert_eq!(
+                plain.as_bytes(),
+                crate::encodings::base32_decode(encoded).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn base32_decode_unpadded_lower_case() {
+        let decoded = crate::encodings::base32_decode("mzxw6ytboi").unwrap();
+        assert_eq!("foobar".as_bytes(), decoded);
+    }
+
+    #[test]
+    fn base32_decode_with_newline() {
+        let decoded = crate::encodings::base32_decode("MZXW6\nYTBOI======").unwrap();
+        assert_eq!("foobar".as_bytes(), decoded);
+    }
+
+    #[test]
+    fn base32_decode_errors() {
+        use crate::encodings::Base32Error;
+
+        assert_eq!(
+            Err(Base32Error::InvalidCharacter {
+                offset: 1,
+                character: '1'
+            }),
+            crate::encodings::base32_decode("M1")
+        );
+        assert_eq!(
+            Err(Base32Error::InvalidLength(3)),
+            crate::encodings::base32_decode("MZX")
+        );
+        assert_eq!(
+            Err(Base32Error::InvalidPadding),
+            crate::encodings::base32_decode("MY=")
+        );
+    }
+}
@@ -0,0 +1,132 @@
+use std::fmt;
+
+use super::WhitespacePolicy;
+
+const ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Base58Error {
+    /// A character outside the alphabet, and its byte offset in the input.
+    InvalidCharacter { offset: usize, character: char },
+}
+
+impl fmt::Display for Base58Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Base58Error::InvalidCharacter { offset, character } => {
+                write!(
+                    f,
+                    "invalid Base58 character {:?} at offset {}",
+                    character, offset
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for Base58Error {}
+
+fn find_index(c: char) -> Option<u8> {
+    if !c.is_ascii() {
+        return None;
+    }
+    ALPHABET.iter().position(|a| *a == c as u8).map(|i| i as u8)
+}
+
+// Encode using the Bitcoin Base58 alphabet. Each leading zero byte becomes a leading '1'.
+pub fn base58_encode(bytes: &[u8]) -> String {
+    let zeros = bytes.iter().take_while(|b| **b == 0).count();
+
+    // Little-endian base 58 digits of the big-endian number in `bytes`.
+    let mut digits: Vec<u8> = Vec::with_capacity(bytes.len() * 138 / 100 + 1);
+    for b in &bytes[zeros..] {
+        let mut carry = *b as u32;
+        for digit in digits.iter_mut() {
+            carry += (*digit as u32) << 8;
+            *digit = (carry % 58) as u8;
+            carry /= 58;
+        }
+        while carry > 0 {
+            digits.push((carry % 58) as u8);
+            carry /= 58;
+        }
+    }
+
+    let mut s = "1".repeat(zeros);
+    s.extend(digits.iter().rev().map(|d| ALPHABET[*d as usize] as char));
+    s
+}
+
+pub fn base58_decode(s: &str) -> Result<Vec<u8>, Base58Error> {
+    let mut zeros = 0;
+    let mut leading = true;
+    // Little-endian base 256 digits.
+    let mut bytes: Vec<u8> = Vec::with_capacity(s.len() * 733 / 1000 + 1);
+    for (offset, c) in s.char_indices() {
+        if WhitespacePolicy::SkipControl.skips(c) {
+            continue;
+        }
+        let index = find_index(c).ok_or(Base58Error::InvalidCharacter {
+            offset,
+            character: c,
+        })?;
+        if leading && index == 0 {
+            zeros += 1;
+            continue;
+        }
+        leading = false;
+
+        let mut carry = index as u32;
+        for b in bytes.iter_mut() {
+            carry += (*b as u32) * 58;
+            *b = carry as u8;
+            carry >>= 8;
+        }
+        while carry > 0 {
+            bytes.push(carry as u8);
+            carry >>= 8;
+        }
+    }
+
+    let mut decoded = vec![0; zeros];
+    decoded.extend(bytes.iter().rev());
+    Ok(decoded)
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn base58_encode_string() {
+        let encoded = crate::encodings::base58_encode("Hello World!".as_bytes());
+        assert_eq!("2NEpo7TZRRrLZSi2U", encoded);
+    }
+
+    #[test]
+    fn base58_leading_zeros() {
+        let bytes = [0, 0, 0x28, 0x7f, 0xb4, 0xcd];
+        let encoded = crate::encodings::base58_encode(&bytes);
+        assert_eq!("11233QC4", encoded);
+        assert_eq!(
+            bytes.to_vec(),
+            crate::encodings::base58_decode(&encoded).unwrap()
+        );
+    }
+
+    #[test]
+    fn base58_encode_and_decode_matches_original() {
+        let bytes: Vec<u8> = (0..=255).collect();
+        let encoded = crate::encodings::base58_encode(&bytes);
+        assert_eq!(bytes, crate::encodings::base58_decode(&encoded).unwrap());
+    }
+
+    #[test]
+    fn base58_decode_invalid_character() {
+        assert_eq!(
+            Err(crate::encodings::Base58Error::InvalidCharacter {
+                offset: 2,
+                character: '0'
+            }),
+            crate::encodings::base58_decode("2N0")
+        );
+    }
+}
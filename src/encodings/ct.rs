@@ -0,0 +1,275 @@
+use super::{Base64Engine, Base64Error, Base64Padding, HexCase, HexError};
+
+const LOWER_HEX: &[u8; 16] = b"0123456789abcdef";
+const UPPER_HEX: &[u8; 16] = b"0123456789ABCDEF";
+
+// 0xFF if `a == b`, otherwise 0, without branching.
+fn ct_eq(a: u8, b: u8) -> u8 {
+    let x = (a ^ b) as u16;
+    (x.wrapping_sub(1) >> 8) as u8
+}
+
+// Widen a 0x00/0xFF mask to 64 bits.
+fn mask64(mask: u8) -> u64 {
+    mask as u64 * 0x0101_0101_0101_0101
+}
+
+// Look up `table[index]` by touching every entry, so the memory access pattern doesn't depend on
+// `index`.
+fn ct_lookup(table: &[u8], index: u8) -> u8 {
+    table
+        .iter()
+        .enumerate()
+        .fold(0, |acc, (i, c)| acc | (c & ct_eq(i as u8, index)))
+}
+
+// Find `c` in `table` by comparing against every entry. Returns the index and a mask that is 0xFF
+// if `c` was found.
+fn ct_position(table: &[u8], c: u8) -> (u8, u8) {
+    table
+        .iter()
+        .enumerate()
+        .fold((0, 0), |(index, found), (i, t)| {
+            let eq = ct_eq(*t, c);
+            (index | (i as u8 & eq), found | eq)
+        })
+}
+
+// Hex encode without secret-dependent table indexing. The output is identical to
+// `hex_encode_with_case`.
+pub fn hex_encode_constant_time(bytes: &[u8], case: HexCase) -> String {
+    let table = match case {
+        HexCase::Lower => LOWER_HEX,
+        HexCase::Upper => UPPER_HEX,
+    };
+    let mut s = String::with_capacity(bytes.len() * 2);
+    for b in bytes {
+        s.push(ct_lookup(table, b >> 4) as char);
+        s.push(ct_lookup(table, b & 0xf) as char);
+    }
+    s
+}
+
+// Hex decode without secret-dependent branching. Unlike `hex_decode`, whitespace is not skipped,
+// and the whole input is processed before any error is reported.
+pub fn hex_decode_constant_time(hex: &str) -> Result<Vec<u8>, HexError> {
+    let digits = hex.as_bytes();
+    let mut bytes = Vec::with_capacity(digits.len() / 2);
+    let mut valid = 0xFF;
+    let mut first_invalid = 0;
+    for (i, pair) in digits.chunks(2).enumerate() {
+        let mut b = 0;
+        for (j, c) in pair.iter().enumerate() {
+            let (lower, in_lower) = ct_position(LOWER_HEX, *c);
+            let (upper, in_upper) = ct_position(UPPER_HEX, *c);
+            let found = in_lower | in_upper;
+            b = b << 4 | (lower & in_lower) | (upper & !in_lower & in_upper);
+            // Remember the offset of the first invalid digit only.
+            let offset = (2 * i + j) as u64;
+            first_invalid |= offset & mask64(valid & !found);
+            valid &= found;
+        }
+        bytes.push(b);
+    }
+
+    if valid == 0 {
+        let offset = first_invalid as usize;
+        return Err(HexError::InvalidDigit {
+            offset,
+            character: hex[offset..].chars().next().unwrap(),
+        });
+    }
+    if !digits.len().is_multiple_of(2) {
+        return Err(HexError::OddLength(digits.len()));
+    }
+    Ok(bytes)
+}
+
+impl Base64Engine {
+    // Encode without secret-dependent table indexing. The output is identical to `encode`.
+    pub fn encode_constant_time(&self, bytes: &[u8]) -> String {
+        let mut s = String::with_capacity(bytes.len().div_ceil(3) * 4);
+        for chunk in bytes.chunks(3) {
+            let mut u = (chunk[0] as u32) << 16;
+            if let Some(b) = chunk.get(1) {
+                u |= (*b as u32) << 8;
+            }
+            if let Some(b) = chunk.get(2) {
+                u |= *b as u32;
+            }
+
+            let symbols = chunk.len() + 1;
+            for i in 0..4 {
+                if i < symbols {
+                    let index = ((u >> (18 - 6 * i)) & 63) as u8;
+                    s.push(ct_lookup(&self.alphabet, index) as char);
+                } else if self.padding != Base64Padding::Forbidden {
+                    s.push('=');
+                }
+            }
+        }
+
+        match self.wrap {
+            Some(wrap) => super::wrap_lines(&s, wrap),
+            None => s,
+        }
+    }
+
+    // Decode without secret-dependent table indexing or branching. Only the amount of trailing
+    // padding, which follows from the public length, is examined with ordinary comparisons.
+    // Unlike `decode`, whitespace is not skipped, and the whole input is processed before any
+    // error is reported.
+    pub fn decode_constant_time(&self, s: &str) -> Result<Vec<u8>, Base64Error> {
+        let input = s.as_bytes();
+        let padding = input.iter().rev().take_while(|c| **c == b'=').count();
+        let symbols = &input[..input.len() - padding];
+
+        let mut decoded = Vec::with_capacity(symbols.len() / 4 * 3 + 2);
+        // Offsets of the first invalid character and of the first `=` before the trailing
+        // padding, and masks saying whether each has been seen.
+        let (mut first_invalid, mut seen_invalid) = (0, 0);
+        let (mut first_padding, mut seen_padding) = (0, 0);
+        let mut unused = 0;
+        for (i, group) in symbols.chunks(4).enumerate() {
+            let mut u: u32 = 0;
+            for (j, c) in group.iter().enumerate() {
+                let (index, found) = ct_position(&self.alphabet, *c);
+                u |= ((index & found) as u32) << (18 - 6 * j);
+
+                let offset = (4 * i + j) as u64;
+                let is_padding = ct_eq(*c, b'=');
+                let invalid = !found & !is_padding;
+                first_invalid |= offset & mask64(invalid & !seen_invalid);
+                seen_invalid |= invalid;
+                first_padding |= offset & mask64(is_padding & !seen_padding);
+                seen_padding |= is_padding;
+            }
+            let len = group.len() - 1;
+            let bytes = [(u >> 16) as u8, (u >> 8) as u8, u as u8];
+            decoded.extend_from_slice(&bytes[..len]);
+            if len < 3 {
+                unused = u & ((1 << (24 - 8 * len)) - 1);
+            }
+        }
+
+        // Report errors in the order `decode` would find them.
+        if seen_invalid != 0 && (seen_padding == 0 || first_invalid < first_padding) {
+            let offset = first_invalid as usize;
+            return Err(Base64Error::InvalidCharacter {
+                offset,
+                character: s[offset..].chars().next().unwrap(),
+            });
+        }
+        if seen_padding != 0 {
+            return Err(Base64Error::InvalidPadding);
+        }
+
+        let remainder = symbols.len() % 4;
+        if remainder == 1 {
+            return Err(Base64Error::InvalidLength(input.len()));
+        }
+        let expected_padding = (4 - remainder) % 4;
+        match self.padding {
+            Base64Padding::Required if padding != expected_padding => {
+                return Err(if padding == 0 {
+                    Base64Error::InvalidLength(symbols.len())
+                } else {
+                    Base64Error::InvalidPadding
+                });
+            }
+            Base64Padding::Optional if padding != 0 && padding != expected_padding => {
+                return Err(Base64Error::InvalidPadding);
+            }
+            Base64Padding::Forbidden if padding != 0 => return Err(Base64Error::InvalidPadding),
+            _ => {}
+        }
+
+        if self.strict && unused != 0 {
+            return Err(Base64Error::NonCanonical {
+                offset: symbols.len() - 1,
+            });
+        }
+        Ok(decoded)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rand::Rng;
+
+    use crate::encodings::{HexCase, MIME, STANDARD, URL_SAFE, URL_SAFE_NO_PAD};
+
+    fn random_bytes(len: usize) -> Vec<u8> {
+        (0..len).map(|_| rand::random()).collect()
+    }
+
+    #[test]
+    fn hex_matches_fast_path() {
+        for len in 0..64 {
+            let bytes = random_bytes(len);
+            for case in [HexCase::Lower, HexCase::Upper] {
+                let encoded = crate::encodings::hex_encode_constant_time(&bytes, case);
+                assert_eq!(
+                    crate::encodings::hex_encode_with_case(&bytes, case),
+                    encoded
+                );
+                assert_eq!(
+                    crate::encodings::hex_decode(&encoded),
+                    crate::encodings::hex_decode_constant_time(&encoded)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn hex_errors_match_fast_path() {
+        for hex in ["abc", "zz", "0g12", "12\u{e9}4", "0123456789abcdeF0"] {
+            assert_eq!(
+                crate::encodings::hex_decode(hex),
+                crate::encodings::hex_decode_constant_time(hex),
+                "{}",
+                hex
+            );
+        }
+    }
+
+    #[test]
+    fn base64_matches_fast_path() {
+        for engine in [STANDARD, URL_SAFE, URL_SAFE_NO_PAD, MIME] {
+            for len in 0..100 {
+                let bytes = random_bytes(len);
+                let encoded = engine.encode_constant_time(&bytes);
+                assert_eq!(engine.encode(&bytes), encoded);
+                if engine.line_wrap().is_none() {
+                    assert_eq!(
+                        engine.decode(&encoded),
+                        engine.decode_constant_time(&encoded)
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn base64_errors_match_fast_path() {
+        let strict = STANDARD.with_strict(true);
+        let mut rng = rand::thread_rng();
+        let mut inputs: Vec<String> = ["QR==", "ab-d", "abc", "a===", "QUJD\u{e9}EF", "QUJDR"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        for _ in 0..200 {
+            let mut s = STANDARD.encode(&random_bytes(rng.gen_range(0..20)));
+            if !s.is_empty() {
+                let i = rng.gen_range(0..s.len());
+                s.replace_range(i..i + 1, ["*", "A", "=", "/"][rng.gen_range(0..4)]);
+            }
+            inputs.push(s);
+        }
+        for s in inputs {
+            for engine in [STANDARD, strict, URL_SAFE_NO_PAD] {
+                assert_eq!(engine.decode(&s), engine.decode_constant_time(&s), "{}", s);
+            }
+        }
+    }
+}
@@ -0,0 +1,202 @@
+use super::{base32_decode, hex_decode_with, Base64Padding, WhitespacePolicy, STANDARD, URL_SAFE};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Hex,
+    Base64,
+    Base64UrlSafe,
+    Base32,
+    /// Not text in any recognised encoding; the bytes are returned unchanged.
+    Binary,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Detected {
+    pub encoding: Encoding,
+    /// How likely it is, from 0 to 1, that the input really is in `encoding` rather than being
+    /// text that only happens to fit its alphabet.
+    pub confidence: f32,
+    pub bytes: Vec<u8>,
+}
+
+// Probability that `symbols` characters drawn uniformly from a larger alphabet all fall in an
+// alphabet of `size` characters, subtracted from 1.
+fn confidence(size: f32, larger: f32, symbols: usize) -> f32 {
+    1.0 - (size / larger).powi(symbols.min(i32::MAX as usize) as i32)
+}
+
+// Guess the encoding of `data`, trying the most specific alphabets first, and decode it. Line
+// breaks and other whitespace are ignored. Data that isn't valid text in any of the encodings is
+// treated as raw binary.
+pub fn detect_and_decode(data: &[u8]) -> Detected {
+    let binary = Detected {
+        encoding: Encoding::Binary,
+        confidence: if data.is_empty() { 0.0 } else { 1.0 },
+        bytes: data.to_vec(),
+    };
+    let text = match std::str::from_utf8(data) {
+        Ok(text) => text,
+        Err(_) => return binary,
+    };
+    let symbols = text
+        .chars()
+        .filter(|c| !WhitespacePolicy::SkipAll.skips(*c) && *c != '=')
+        .count();
+    if symbols == 0 {
+        return binary;
+    }
+
+    if let Ok(bytes) = hex_decode_with(text, WhitespacePolicy::SkipAll) {
+        return Detected {
+            encoding: Encoding::Hex,
+            confidence: confidence(16.0, 64.0, symbols),
+            bytes,
+        };
+    }
+    let stripped = strip_whitespace(text);
+    let base32 = base32_detect(&stripped, symbols);
+    // Unpadded Base32 whose length isn't a whole number of groups is just as likely to be Base64,
+    // so only fall back to it if Base64 can't decode the input.
+    if stripped.len().is_multiple_of(8) {
+        if let Some(detected) = base32 {
+            return detected;
+        }
+    }
+
+    let url_safe = text.contains(['-', '_']);
+    let (encoding, engine) = if url_safe {
+        (Encoding::Base64UrlSafe, URL_SAFE)
+    } else {
+        (Encoding::Base64, STANDARD)
+    };
+    let engine = engine
+        .with_padding(Base64Padding::Optional)
+        .with_whitespace(WhitespacePolicy::SkipAll);
+    if let Ok(bytes) = engine.decode(text) {
+        return Detected {
+            encoding,
+            confidence: confidence(64.0, 95.0, symbols),
+            bytes,
+        };
+    }
+    if let Some(detected) = base32 {
+        return detected;
+    }
+
+    binary
+}
+
+// Decode `text` as Base32 if its letters are all the same case. Lowercase Base32 is unusual and
+// lowercase words fit its alphabet, so it is trusted half as much as uppercase.
+fn base32_detect(text: &str, symbols: usize) -> Option<Detected> {
+    let has_upper = text.chars().any(|c| c.is_ascii_uppercase());
+    let has_lower = text.chars().any(|c| c.is_ascii_lowercase());
+    if has_upper && has_lower {
+        return None;
+    }
+    let bytes = base32_decode(text).ok()?;
+    let confidence = confidence(32.0, 64.0, symbols);
+    Some(Detected {
+        encoding: Encoding::Base32,
+        confidence: if has_lower {
+            confidence / 2.0
+        } else {
+            confidence
+        },
+        bytes,
+    })
+}
+
+// Decode each non-blank line of `text` independently, as in files with one ciphertext per line.
+pub fn detect_and_decode_lines(text: &str) -> impl Iterator<Item = Detected> + '_ {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| detect_and_decode(line.as_bytes()))
+}
+
+fn strip_whitespace(text: &str) -> String {
+    text.chars()
+        .filter(|c| !WhitespacePolicy::SkipAll.skips(*c))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::encodings::Encoding;
+
+    #[test]
+    fn detect_hex_lines() {
+        let file_contents = std::fs::read_to_string("data/4.txt").unwrap();
+        let lines: Vec<_> = crate::encodings::detect_and_decode_lines(&file_contents).collect();
+        assert_eq!(file_contents.lines().count(), lines.len());
+        for (detected, line) in lines.iter().zip(file_contents.lines()) {
+            assert_eq!(Encoding::Hex, detected.encoding);
+            assert!(detected.confidence > 0.99);
+            assert_eq!(crate::encodings::hex_decode(line).unwrap(), detected.bytes);
+        }
+    }
+
+    #[test]
+    fn detect_wrapped_base64() {
+        let file_contents = std::fs::read_to_string("data/6.txt").unwrap();
+        let detected = crate::encodings::detect_and_decode(file_contents.as_bytes());
+        assert_eq!(Encoding::Base64, detected.encoding);
+        assert!(detected.confidence > 0.99);
+        assert_eq!(
+            crate::encodings::base64_decode(&file_contents).unwrap(),
+            detected.bytes
+        );
+    }
+
+    #[test]
+    fn detect_url_safe_base64() {
+        let token = crate::encodings::URL_SAFE_NO_PAD.encode(&[0xfb, 0xff, 0xbf, 0x3e, 0x00]);
+        let detected = crate::encodings::detect_and_decode(token.as_bytes());
+        assert_eq!(Encoding::Base64UrlSafe, detected.encoding);
+        assert_eq!(vec![0xfb, 0xff, 0xbf, 0x3e, 0x00], detected.bytes);
+    }
+
+    #[test]
+    fn detect_base32() {
+        let detected = crate::encodings::detect_and_decode(b"JBSWY3DPEHPK3PXP");
+        assert_eq!(Encoding::Base32, detected.encoding);
+        assert_eq!(b"Hello!\xde\xad\xbe\xef".to_vec(), detected.bytes);
+    }
+
+    #[test]
+    fn detect_unpadded_base32() {
+        let detected = crate::encodings::detect_and_decode(b"JBSWY3DPEHPK3");
+        assert_eq!(Encoding::Base32, detected.encoding);
+        assert_eq!(b"Hello!\xde\xad".to_vec(), detected.bytes);
+
+        let detected = crate::encodings::detect_and_decode(b"jbswy3dpehpk3pxp");
+        assert_eq!(Encoding::Base32, detected.encoding);
+        assert!(detected.confidence < 0.5);
+        assert_eq!(b"Hello!\xde\xad\xbe\xef".to_vec(), detected.bytes);
+    }
+
+    #[test]
+    fn detect_base64_that_fits_base32_alphabet() {
+        let detected = crate::encodings::detect_and_decode(b"QUJD");
+        assert_eq!(Encoding::Base64, detected.encoding);
+        assert_eq!(b"ABC".to_vec(), detected.bytes);
+
+        let detected = crate::encodings::detect_and_decode(b"helloworld");
+        assert_eq!(Encoding::Base64, detected.encoding);
+
+        let detected = crate::encodings::detect_and_decode(b"JBSWy3DPEHPK3PXP");
+        assert_eq!(Encoding::Base64, detected.encoding);
+    }
+
+    #[test]
+    fn detect_binary() {
+        let bytes = [0x00, 0xff, 0x10, 0x80];
+        let detected = crate::encodings::detect_and_decode(&bytes);
+        assert_eq!(Encoding::Binary, detected.encoding);
+        assert_eq!(bytes.to_vec(), detected.bytes);
+
+        let detected = crate::encodings::detect_and_decode(b"not encoded, just text.");
+        assert_eq!(Encoding::Binary, detected.encoding);
+    }
+}
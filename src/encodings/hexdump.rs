@@ -0,0 +1,272 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+
+const HIGHLIGHT: &str = "\x1b[7m";
+const CHANGED: &str = "\x1b[31m";
+const RESET: &str = "\x1b[0m";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HexdumpOptions {
+    /// Number of bytes shown on each line.
+    pub row_width: usize,
+    /// Cipher block size; boundaries inside a row are marked with `|`, and blocks that occur more
+    /// than once are flagged. `None` disables both.
+    pub block_size: Option<usize>,
+    /// Use ANSI escape codes to highlight repeated blocks and changed bytes instead of textual
+    /// markers.
+    pub color: bool,
+}
+
+impl Default for HexdumpOptions {
+    fn default() -> Self {
+        HexdumpOptions {
+            row_width: 16,
+            block_size: Some(16),
+            color: false,
+        }
+    }
+}
+
+// Format `bytes` like `xxd`, with 16-byte AES block boundaries marked and repeated blocks flagged.
+pub fn hexdump(bytes: &[u8]) -> String {
+    hexdump_with(bytes, &HexdumpOptions::default())
+}
+
+pub fn hexdump_with(bytes: &[u8], options: &HexdumpOptions) -> String {
+    let options = &normalize(options);
+    let row_width = options.row_width.max(1);
+    let repeats = repeated_blocks(bytes, options.block_size);
+    let repeated = |i: usize| {
+        options
+            .block_size
+            .is_some_and(|size| repeats.contains_key(&(i / size)))
+    };
+
+    let mut s = String::new();
+    for (row, chunk) in bytes.chunks(row_width).enumerate() {
+        let start = row * row_width;
+        write!(s, "{:08x}: ", start).unwrap();
+        s.push_str(&hex_cells(
+            chunk, start, row_width, options, repeated, HIGHLIGHT,
+        ));
+        s.push_str("  ");
+        s.push_str(&ascii_column(chunk));
+
+        if let Some(size) = options.block_size {
+            // Annotate repeated blocks that start on this row.
+            for block in start.div_ceil(size)..(start + chunk.len()).div_ceil(size) {
+                if let Some(first) = repeats.get(&block).filter(|first| **first != block) {
+                    write!(s, "  [block {} = block {}]", block, first).unwrap();
+                }
+            }
+        }
+        s.push('\n');
+    }
+    s
+}
+
+// Format two buffers side by side, flagging the bytes that differ between them, such as those
+// changed by a CBC bit-flip.
+pub fn hexdump_diff(a: &[u8], b: &[u8], options: &HexdumpOptions) -> String {
+    let options = &normalize(options);
+    let row_width = options.row_width.max(1);
+    let differs = |i: usize| a.get(i) != b.get(i);
+    let rows = a.len().max(b.len()).div_ceil(row_width);
+
+    let mut s = String::new();
+    for row in 0..rows {
+        let start = row * row_width;
+        let left = row_slice(a, start, row_width);
+        let right = row_slice(b, start, row_width);
+        let left_cells = hex_cells(left, start, row_width, options, differs, CHANGED);
+        let right_cells = hex_cells(right, start, row_width, options, differs, CHANGED);
+
+        writeln!(s, "{:08x}: {} || {}", start, left_cells, right_cells).unwrap();
+        if !options.color && (start..start + row_width).any(differs) {
+            // Put a caret under each changed byte on both sides.
+            let markers = marker_cells(start, row_width, options.block_size, differs);
+            writeln!(s, "          {} || {}", markers, markers).unwrap();
+        }
+    }
+    s
+}
+
+// Treat a block size of zero as no block size, so that later code never divides by zero.
+fn normalize(options: &HexdumpOptions) -> HexdumpOptions {
+    HexdumpOptions {
+        block_size: options.block_size.filter(|size| *size > 0),
+        ..*options
+    }
+}
+
+fn row_slice(bytes: &[u8], start: usize, row_width: usize) -> &[u8] {
+    let start = start.min(bytes.len());
+    &bytes[start..(start + row_width).min(bytes.len())]
+}
+
+// Map the index of each block that occurs more than once to the index of its first occurrence.
+fn repeated_blocks(bytes: &[u8], block_size: Option<usize>) -> HashMap<usize, usize> {
+    let size = match block_size {
+        Some(size) => size,
+        None => return HashMap::new(),
+    };
+    let mut first_seen: HashMap<&[u8], usize> = HashMap::new();
+    let mut counts: HashMap<&[u8], usize> = HashMap::new();
+    for (i, block) in bytes.chunks_exact(size).enumerate() {
+        first_seen.entry(block).or_insert(i);
+        *counts.entry(block).or_default() += 1;
+    }
+    bytes
+        .chunks_exact(size)
+        .enumerate()
+        .filter(|(_, block)| counts[block] > 1)
+        .map(|(i, block)| (i, first_seen[block]))
+        .collect()
+}
+
+fn is_block_boundary(i: usize, start: usize, block_size: Option<usize>) -> bool {
+    i != start && block_size.is_some_and(|size| i.is_multiple_of(size))
+}
+
+// Render a row of hex cells, padded out to `row_width` so that later columns line up.
+fn hex_cells(
+    chunk: &[u8],
+    start: usize,
+    row_width: usize,
+    options: &HexdumpOptions,
+    flagged: impl Fn(usize) -> bool,
+    color: &str,
+) -> String {
+    let mut s = String::new();
+    for offset in 0..row_width {
+        let i = start + offset;
+        if is_block_boundary(i, start, options.block_size) {
+            s.push_str("| ");
+        }
+        match chunk.get(offset) {
+            Some(b) if options.color && flagged(i) => {
+                write!(s, "{}{:02x}{} ", color, b, RESET).unwrap();
+            }
+            Some(b) => write!(s, "{:02x} ", b).unwrap(),
+            None => s.push_str("   "),
+        }
+    }
+    s.pop();
+    s
+}
+
+fn marker_cells(
+    start: usize,
+    row_width: usize,
+    block_size: Option<usize>,
+    flagged: impl Fn(usize) -> bool,
+) -> String {
+    let mut s = String::new();
+    for i in start..start + row_width {
+        if is_block_boundary(i, start, block_size) {
+            s.push_str("  ");
+        }
+        s.push_str(if flagged(i) { "^^ " } else { "   " });
+    }
+    s.pop();
+    s
+}
+
+fn ascii_column(chunk: &[u8]) -> String {
+    chunk
+        .iter()
+        .map(|b| {
+            if b.is_ascii_graphic() || *b == b' ' {
+                *b as char
+            } else {
+                '.'
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn hexdump_rows_and_ascii_column() {
+        let dump = crate::encodings::hexdump("I'm killing your brain".as_bytes());
+        let lines: Vec<&str> = dump.lines().collect();
+        assert_eq!(2, lines.len());
+        assert_eq!(
+            "00000000: 49 27 6d 20 6b 69 6c 6c 69 6e 67 20 79 6f 75 72  I'm killing your",
+            lines[0]
+        );
+        assert!(lines[1].starts_with("00000010: 20 62 72 61 69 6e    "));
+        assert!(lines[1].ends_with(" brain"));
+    }
+
+    #[test]
+    fn hexdump_marks_block_boundaries() {
+        let options = crate::encodings::HexdumpOptions {
+            row_width: 8,
+            block_size: Some(4),
+            color: false,
+        };
+        let bytes: Vec<u8> = (0..8).collect();
+        let dump = crate::encodings::hexdump_with(&bytes, &options);
+        assert!(dump.starts_with("00000000: 00 01 02 03 | 04 05 06 07 "));
+    }
+
+    #[test]
+    fn hexdump_flags_repeated_blocks() {
+        let key = "YELLOW SUBMARINE".as_bytes();
+        let plaintext = [b'A'; 48];
+        let encrypted = crate::aes::encrypt_aes128_ecb(&plaintext, key).unwrap();
+        let dump = crate::encodings::hexdump(&encrypted);
+        let lines: Vec<&str> = dump.lines().collect();
+        assert!(!lines[0].contains("[block"));
+        assert!(lines[1].ends_with("[block 1 = block 0]"));
+        assert!(lines[2].ends_with("[block 2 = block 0]"));
+        assert!(!lines[3].contains("[block"));
+
+        let options = crate::encodings::HexdumpOptions {
+            color: true,
+            ..Default::default()
+        };
+        let dump = crate::encodings::hexdump_with(&encrypted, &options);
+        assert!(dump.lines().next().unwrap().contains("\x1b[7m"));
+        assert!(!dump.lines().nth(3).unwrap().contains("\x1b[7m"));
+    }
+
+    #[test]
+    fn hexdump_ignores_zero_block_size() {
+        let options = crate::encodings::HexdumpOptions {
+            block_size: Some(0),
+            color: true,
+            ..Default::default()
+        };
+        let bytes = [0u8; 32];
+        let dump = crate::encodings::hexdump_with(&bytes, &options);
+        assert!(!dump.contains('|'));
+        assert!(!dump.contains("\x1b[7m"));
+        let diff = crate::encodings::hexdump_diff(&bytes, &[1u8; 32], &options);
+        assert_eq!(2, diff.lines().count());
+    }
+
+    #[test]
+    fn hexdump_diff_marks_changed_bytes() {
+        let a = [0u8; 20];
+        let mut b = a;
+        b[1] ^= 0x01;
+        b[17] ^= 0x80;
+        let options = crate::encodings::HexdumpOptions {
+            row_width: 16,
+            block_size: None,
+            color: false,
+        };
+        let diff = crate::encodings::hexdump_diff(&a, &b, &options);
+        let lines: Vec<&str> = diff.lines().collect();
+        assert_eq!(4, lines.len());
+        assert!(lines[0].contains(" || 00 01 00"));
+        let markers: Vec<&str> = lines[1].split(" || ").collect();
+        assert_eq!("             ^^", markers[0].trim_end());
+        assert_eq!("   ^^", markers[1].trim_end());
+        assert!(lines[2].starts_with("00000010: 00 00 00 00"));
+        assert!(lines[2].contains("|| 00 80 00 00"));
+    }
+}
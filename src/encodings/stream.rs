@@ -0,0 +1,341 @@
+use std::io::{self, Read, Write};
+
+use super::{Base64DecodeState, Base64Engine, HexCase, HexDecodeState, WhitespacePolicy};
+
+const CHUNK_SIZE: usize = 4096;
+
+fn invalid_data<E>(e: E) -> io::Error
+where
+    E: std::error::Error + Send + Sync + 'static,
+{
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+/// Base64 encodes everything written to it and writes the result to `inner`.
+///
+/// Bytes that don't fill a whole quantum are held back until more arrive, so `finish` must be
+/// called to write the final, possibly padded, quantum.
+pub struct Base64Encoder<W: Write> {
+    inner: W,
+    engine: Base64Engine,
+    pending: Vec<u8>,
+    column: usize,
+}
+
+impl<W: Write> Base64Encoder<W> {
+    pub fn new(inner: W, engine: &Base64Engine) -> Self {
+        Base64Encoder {
+            inner,
+            engine: *engine,
+            pending: Vec::with_capacity(3),
+            column: 0,
+        }
+    }
+
+    // Write out any remaining partial quantum and return the underlying writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        if !self.pending.is_empty() {
+            let mut s = String::with_capacity(4);
+            self.engine.encode_quantum(&self.pending, &mut s);
+            self.pending.clear();
+            self.write_encoded(&s)?;
+        }
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+
+    fn write_encoded(&mut self, s: &str) -> io::Result<()> {
+        let wrap = match self.engine.line_wrap() {
+            Some(wrap) if wrap.width > 0 => wrap,
+            _ => return self.inner.write_all(s.as_bytes()),
+        };
+
+        let mut wrapped = String::with_capacity(s.len() + s.len() / wrap.width * wrap.ending.len());
+        for c in s.chars() {
+            if self.column == wrap.width {
+                wrapped.push_str(wrap.ending);
+                self.column = 0;
+            }
+            wrapped.push(c);
+            self.column += 1;
+        }
+        self.inner.write_all(wrapped.as_bytes())
+    }
+}
+
+impl<W: Write> Write for Base64Encoder<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.pending.extend_from_slice(buf);
+        let whole = self.pending.len() / 3 * 3;
+        let mut s = String::with_capacity(whole / 3 * 4);
+        for chunk in self.pending[..whole].chunks(3) {
+            self.engine.encode_quantum(chunk, &mut s);
+        }
+        self.pending.drain(..whole);
+        self.write_encoded(&s)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Reads Base64 text from `inner` and yields the decoded bytes.
+///
+/// Malformed input is reported as an `io::ErrorKind::InvalidData` error wrapping a
+/// `Base64Error`, whose offsets count bytes from the start of the stream.
+pub struct Base64Decoder<R: Read> {
+    inner: R,
+    state: Option<Base64DecodeState>,
+    offset: usize,
+    decoded: Vec<u8>,
+    position: usize,
+}
+
+impl<R: Read> Base64Decoder<R> {
+    pub fn new(inner: R, engine: &Base64Engine) -> Self {
+        Base64Decoder {
+            inner,
+            state: Some(Base64DecodeState::new(engine)),
+            offset: 0,
+            decoded: Vec::new(),
+            position: 0,
+        }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: Read> Read for Base64Decoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.position == self.decoded.len() {
+            let state = match self.state.as_mut() {
+                Some(state) => state,
+                None => return Ok(0),
+            };
+            self.decoded.clear();
+            self.position = 0;
+
+            let mut chunk = [0; CHUNK_SIZE];
+            let n = self.inner.read(&mut chunk)?;
+            if n == 0 {
+                let state = self.state.take().unwrap();
+                state.finish(&mut self.decoded).map_err(invalid_data)?;
+                continue;
+            }
+            for b in &chunk[..n] {
+                state
+                    .push(self.offset, *b as char, &mut self.decoded)
+                    .map_err(invalid_data)?;
+                self.offset += 1;
+            }
+        }
+
+        let n = buf.len().min(self.decoded.len() - self.position);
+        buf[..n].copy_from_slice(&self.decoded[self.position..self.position + n]);
+        self.position += n;
+        Ok(n)
+    }
+}
+
+/// Hex encodes everything written to it and writes the result to `inner`.
+pub struct HexEncoder<W: Write> {
+    inner: W,
+    case: HexCase,
+}
+
+impl<W: Write> HexEncoder<W> {
+    pub fn new(inner: W, case: HexCase) -> Self {
+        HexEncoder { inner, case }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: Write> Write for HexEncoder<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let hex = super::hex_encode_with_case(buf, self.case);
+        self.inner.write_all(hex.as_bytes())?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Reads hex text from `inner` and yields the decoded bytes.
+///
+/// Malformed input is reported as an `io::ErrorKind::InvalidData` error wrapping a `HexError`,
+/// whose offsets count bytes from the start of the stream.
+pub struct HexDecoder<R: Read> {
+    inner: R,
+    state: Option<HexDecodeState>,
+    offset: usize,
+    decoded: Vec<u8>,
+    position: usize,
+}
+
+impl<R: Read> HexDecoder<R> {
+    pub fn new(inner: R, whitespace: WhitespacePolicy) -> Self {
+        HexDecoder {
+            inner,
+            state: Some(HexDecodeState::new(whitespace)),
+            offset: 0,
+            decoded: Vec::new(),
+            position: 0,
+        }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: Read> Read for HexDecoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.position == self.decoded.len() {
+            let state = match self.state.as_mut() {
+                Some(state) => state,
+                None => return Ok(0),
+            };
+            self.decoded.clear();
+            self.position = 0;
+
+            let mut chunk = [0; CHUNK_SIZE];
+            let n = self.inner.read(&mut chunk)?;
+            if n == 0 {
+                self.state.take().unwrap().finish().map_err(invalid_data)?;
+                continue;
+            }
+            for b in &chunk[..n] {
+                if let Some(decoded) = state.push(self.offset, *b as char).map_err(invalid_data)? {
+                    self.decoded.push(decoded);
+                }
+                self.offset += 1;
+            }
+        }
+
+        let n = buf.len().min(self.decoded.len() - self.position);
+        buf[..n].copy_from_slice(&self.decoded[self.position..self.position + n]);
+        self.position += n;
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::{Read, Write};
+
+    // A reader that hands out at most `step` bytes per call, to split quanta across reads.
+    struct Trickle<'a> {
+        bytes: &'a [u8],
+        step: usize,
+    }
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let n = self.step.min(buf.len()).min(self.bytes.len());
+            buf[..n].copy_from_slice(&self.bytes[..n]);
+            self.bytes = &self.bytes[n..];
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn base64_encoder_matches_encode() {
+        let bytes: Vec<u8> = (0..=255).collect();
+        let engine = crate::encodings::MIME;
+        let mut encoder = crate::encodings::Base64Encoder::new(Vec::new(), &engine);
+        for chunk in bytes.chunks(7) {
+            encoder.write_all(chunk).unwrap();
+        }
+        let encoded = encoder.finish().unwrap();
+        assert_eq!(engine.encode(&bytes).as_bytes(), encoded.as_slice());
+    }
+
+    #[test]
+    fn base64_decoder_across_chunk_boundaries() {
+        let base64 = "IlVzZSB0aGUgZm9yY2UsIEhh\ncnJ5ISIgLSBHYW5kYWxm";
+        for step in 1..8 {
+            let reader = Trickle {
+                bytes: base64.as_bytes(),
+                step,
+            };
+            let mut decoder =
+                crate::encodings::Base64Decoder::new(reader, &crate::encodings::STANDARD);
+            let mut decoded = String::new();
+            decoder.read_to_string(&mut decoded).unwrap();
+            assert_eq!("\"Use the force, Harry!\" - Gandalf", decoded);
+        }
+    }
+
+    #[test]
+    fn base64_decoder_reports_stream_offset() {
+        let reader = Trickle {
+            bytes: b"QUJD\nRE*G",
+            step: 3,
+        };
+        let mut decoder = crate::encodings::Base64Decoder::new(reader, &crate::encodings::STANDARD);
+        let err = decoder.read_to_end(&mut Vec::new()).unwrap_err();
+        assert_eq!(std::io::ErrorKind::InvalidData, err.kind());
+        let inner = err.into_inner().unwrap();
+        assert_eq!(
+            &crate::encodings::Base64Error::InvalidCharacter {
+                offset: 7,
+                character: '*'
+            },
+            inner
+                .downcast_ref::<crate::encodings::Base64Error>()
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn hex_round_trip() {
+        let bytes: Vec<u8> = (0..=255).collect();
+        let mut encoder =
+            crate::encodings::HexEncoder::new(Vec::new(), crate::encodings::HexCase::Upper);
+        encoder.write_all(&bytes).unwrap();
+        let hex = encoder.into_inner();
+
+        let reader = Trickle {
+            bytes: &hex,
+            step: 5,
+        };
+        let mut decoder = crate::encodings::HexDecoder::new(
+            reader,
+            crate::encodings::WhitespacePolicy::SkipControl,
+        );
+        let mut decoded = Vec::new();
+        decoder.read_to_end(&mut decoded).unwrap();
+        assert_eq!(bytes, decoded);
+    }
+
+    #[test]
+    fn hex_decoder_odd_length() {
+        let mut decoder = crate::encodings::HexDecoder::new(
+            "abc\n".as_bytes(),
+            crate::encodings::WhitespacePolicy::SkipControl,
+        );
+        let err = decoder.read_to_end(&mut Vec::new()).unwrap_err();
+        assert_eq!(std::io::ErrorKind::InvalidData, err.kind());
+    }
+
+    #[test]
+    fn decrypt_streamed_file() {
+        let file = std::fs::File::open("data/7.txt").unwrap();
+        let mut decoder = crate::encodings::Base64Decoder::new(file, &crate::encodings::STANDARD);
+        let mut bytes = Vec::new();
+        decoder.read_to_end(&mut bytes).unwrap();
+        let decrypted =
+            crate::aes::decrypt_aes128_ecb(&bytes, "YELLOW SUBMARINE".as_bytes()).unwrap();
+        let plaintext = std::str::from_utf8(&decrypted).unwrap();
+        assert!(plaintext.starts_with("I'm back and I'm ringin' the bell"));
+    }
+}
@@ -0,0 +1,297 @@
+use crate::aes::{Aes, CipherError, CtrLayout};
+use crate::cipher::BlockCipher;
+use crate::modes::CtrKeystream;
+
+// The GCM reduction polynomial x^128 + x^7 + x^2 + x + 1, in GCM's reflected bit order.
+const R: u128 = 0xe1 << 120;
+
+// Multiply two elements of GF(2^128) as defined for GCM. Blocks map to `u128` big-endian, so the
+// first bit of a block is the coefficient of x^0. The loop has no data-dependent branches.
+pub fn gf128_mul(x: u128, y: u128) -> u128 {
+    let mut z = 0;
+    let mut v = y;
+    for i in 0..128 {
+        let bit = (x >> (127 - i)) & 1;
+        z ^= v & bit.wrapping_neg();
+        v = (v >> 1) ^ (R & (v & 1).wrapping_neg());
+    }
+    z
+}
+
+fn ghash_update(h: u128, mut y: u128, bytes: &[u8]) -> u128 {
+    for chunk in bytes.chunks(16) {
+        let mut block = [0; 16];
+        block[..chunk.len()].copy_from_slice(chunk);
+        y = gf128_mul(y ^ u128::from_be_bytes(block), h);
+    }
+    y
+}
+
+// GHASH under hash key `h` of `aad` and `ciphertext`, each zero-padded to a whole number of
+// blocks, followed by the block of their lengths in bits.
+pub fn ghash(h: u128, aad: &[u8], ciphertext: &[u8]) -> u128 {
+    let y = ghash_update(h, 0, aad);
+    let y = ghash_update(h, y, ciphertext);
+    let lengths = ((aad.len() as u128 * 8) << 64) | (ciphertext.len() as u128 * 8);
+    gf128_mul(y ^ lengths, h)
+}
+
+// SP 800-38D allows tags of 4 and 8 bytes for special applications, and 12 to 16 bytes otherwise.
+fn check_tag_length(tag_length: usize) -> Result<(), CipherError> {
+    match tag_length {
+        4 | 8 | 12..=16 => Ok(()),
+        len => Err(CipherError::InvalidTagLength(len)),
+    }
+}
+
+// Compare tags by accumulating the XOR of every byte, so timing doesn't reveal where they differ.
+fn tags_match(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+struct Gcm {
+    cipher: Aes,
+    h: u128,
+    // The pre-counter block J0.
+    j0: [u8; 16],
+}
+
+impl Gcm {
+    fn new(key: &[u8], iv: &[u8]) -> Result<Self, CipherError> {
+        if iv.is_empty() {
+            return Err(CipherError::InvalidIvLength(0));
+        }
+        let cipher = Aes::new(key)?;
+        let mut h = [0; 16];
+        cipher.encrypt_block(&mut h);
+        let h = u128::from_be_bytes(h);
+
+        // A 96-bit IV is used directly with a counter of 1; any other length is hashed.
+        let j0 = if iv.len() == 12 {
+            let mut j0 = [0; 16];
+            j0[..12].copy_from_slice(iv);
+            j0[15] = 1;
+            j0
+        } else {
+            ghash(h, &[], iv).to_be_bytes()
+        };
+        Ok(Gcm { cipher, h, j0 })
+    }
+
+    // CTR mode with a 32-bit big-endian counter, starting one past J0.
+    fn apply_keystream(&self, bytes: &[u8]) -> Result<Vec<u8>, CipherError> {
+        let mut keystream = CtrKeystream::new(&self.cipher, &self.j0[..12], CtrLayout::NONCE_96)?;
+        let counter = u32::from_be_bytes([self.j0[12], self.j0[13], self.j0[14], self.j0[15]]);
+        keystream.seek((counter as u64 + 1) * 16);
+        Ok(keystream.zip(bytes).map(|(k, b)| k ^ b).collect())
+    }
+
+    fn tag(&self, aad: &[u8], ciphertext: &[u8], tag_length: usize) -> Vec<u8> {
+        let mut mask = self.j0;
+        self.cipher.encrypt_block(&mut mask);
+        let tag = ghash(self.h, aad, ciphertext) ^ u128::from_be_bytes(mask);
+        tag.to_be_bytes()[..tag_length].to_vec()
+    }
+}
+
+// Encrypt and authenticate using AES-GCM. The output is the ciphertext followed by a tag of
+// `tag_length` bytes. `aad` is authenticated but not encrypted.
+pub fn encrypt_aes_gcm(
+    bytes: &[u8],
+    key: &[u8],
+    iv: &[u8],
+    aad: &[u8],
+    tag_length: usize,
+) -> Result<Vec<u8>, CipherError> {
+    check_tag_length(tag_length)?;
+    let gcm = Gcm::new(key, iv)?;
+    let mut encrypted = gcm.apply_keystream(bytes)?;
+    let tag = gcm.tag(aad, &encrypted, tag_length);
+    encrypted.extend(tag);
+    Ok(encrypted)
+}
+
+// Verify the tag at the end of `encrypted` and only then decrypt.
+pub fn decrypt_aes_gcm(
+    encrypted: &[u8],
+    key: &[u8],
+    iv: &[u8],
+    aad: &[u8],
+    tag_length: usize,
+) -> Result<Vec<u8>, CipherError> {
+    check_tag_length(tag_length)?;
+    let gcm = Gcm::new(key, iv)?;
+    if encrypted.len() < tag_length {
+        return Err(CipherError::AuthenticationFailed);
+    }
+    let (ciphertext, tag) = encrypted.split_at(encrypted.len() - tag_length);
+    if !tags_match(&gcm.tag(aad, ciphertext, tag_length), tag) {
+        return Err(CipherError::AuthenticationFailed);
+    }
+    gcm.apply_keystream(ciphertext)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::aes::CipherError;
+
+    const KEY: &str = "feffe9928665731c6d6a8f9467308308";
+    const PLAINTEXT: &str = "d9313225f88406e5a55909c5aff5269a86a7a9531534f7da2e4c303d8a318a72\
+                             1c3c0c95956809532fcf0e2449a6b525b16aedf5aa0de657ba637b391aafd255";
+    const AAD: &str = "feedfacedeadbeeffeedfacedeadbeefabaddad2";
+
+    fn hex(s: &str) -> Vec<u8> {
+        crate::encodings::hex_decode(s).unwrap()
+    }
+
+    // Check a NIST GCM test case, given as (key, iv, plaintext, aad, ciphertext, tag).
+    fn check_vector(vector: [&str; 6]) {
+        let [key, iv, plaintext, aad, ciphertext, tag] = vector.map(hex);
+        let encrypted = crate::gcm::encrypt_aes_gcm(&plaintext, &key, &iv, &aad, 16).unwrap();
+        assert_eq!(
+            vector[4].to_string() + vector[5],
+            crate::encodings::hex_encode(&encrypted)
+        );
+        assert_eq!(
+            plaintext,
+            crate::gcm::decrypt_aes_gcm(&encrypted, &key, &iv, &aad, 16).unwrap()
+        );
+        assert_eq!(ciphertext.len() + tag.len(), encrypted.len());
+    }
+
+    #[test]
+    fn gcm_nist_vectors() {
+        // Test cases 1 to 6 of the GCM specification, as used in the NIST validation suite.
+        let zero_key = "00000000000000000000000000000000";
+        check_vector([
+            zero_key,
+            "000000000000000000000000",
+            "",
+            "",
+            "",
+            "58e2fccefa7e3061367f1d57a4e7455a",
+        ]);
+        check_vector([
+            zero_key,
+            "000000000000000000000000",
+            "00000000000000000000000000000000",
+            "",
+            "0388dace60b6a392f328c2b971b2fe78",
+            "ab6e47d42cec13bdf53a67b21257bddf",
+        ]);
+        check_vector([
+            KEY,
+            "cafebabefacedbaddecaf888",
+            PLAINTEXT,
+            "",
+            "42831ec2217774244b7221b784d0d49ce3aa212f2c02a4e035c17e2329aca12e\
+             21d514b25466931c7d8f6a5aac84aa051ba30b396a0aac973d58e091473f5985",
+            "4d5c2af327cd64a62cf35abd2ba6fab4",
+        ]);
+        check_vector([
+            KEY,
+            "cafebabefacedbaddecaf888",
+            &PLAINTEXT[..120],
+            AAD,
+            "42831ec2217774244b7221b784d0d49ce3aa212f2c02a4e035c17e2329aca12e\
+             21d514b25466931c7d8f6a5aac84aa051ba30b396a0aac973d58e091",
+            "5bc94fbc3221a5db94fae95ae7121a47",
+        ]);
+        // A 64-bit IV.
+        check_vector([
+            KEY,
+            "cafebabefacedbad",
+            &PLAINTEXT[..120],
+            AAD,
+            "61353b4c2806934a777ff51fa22a4755699b2a714fcdc6f83766e5f97b6c7423\
+             73806900e49f24b22b097544d4896b424989b5e1ebac0f07c23f4598",
+            "3612d2e79e3b0785561be14aaca2fccb",
+        ]);
+        // A 480-bit IV.
+        check_vector([
+            KEY,
+            "9313225df88406e555909c5aff5269aa6a7a9538534f7da1e4c303d2a318a728\
+             c3c0c95156809539fcf0e2429a6b525416aedbf5a0de6a57a637b39b",
+            &PLAINTEXT[..120],
+            AAD,
+            "8ce24998625615b603a033aca13fb894be9112a5c3a211a8ba262a3cca7e2ca7\
+             01e4a9a4fba43c90ccdcb281d48c7c6fd62875d2aca417034c34aee5",
+            "619cc5aefffe0bfa462af43c1699d050",
+        ]);
+    }
+
+    #[test]
+    fn ghash_standalone() {
+        // Test case 2: H = E(K, 0^128) and GHASH(H, {}, C).
+        let h = u128::from_str_radix("66e94bd4ef8a2c3b884cfa59ca342b2e", 16).unwrap();
+        let ciphertext = hex("0388dace60b6a392f328c2b971b2fe78");
+        assert_eq!(
+            0xf38cbb1ad69223dcc3457ae5b6b0f885,
+            crate::gcm::ghash(h, &[], &ciphertext)
+        );
+    }
+
+    #[test]
+    fn gf128_mul_identity_and_commutativity() {
+        // The multiplicative identity is the block with only its first bit set.
+        let one = 1 << 127;
+        let a = 0x66e94bd4ef8a2c3b884cfa59ca342b2e;
+        let b = 0x0388dace60b6a392f328c2b971b2fe78;
+        assert_eq!(a, crate::gcm::gf128_mul(a, one));
+        assert_eq!(crate::gcm::gf128_mul(a, b), crate::gcm::gf128_mul(b, a));
+        assert_eq!(0, crate::gcm::gf128_mul(a, 0));
+    }
+
+    #[test]
+    fn truncated_tags() {
+        let key = hex(KEY);
+        let iv = hex("cafebabefacedbaddecaf888");
+        let aad = hex(AAD);
+        let encrypted =
+            crate::gcm::encrypt_aes_gcm(b"attack at dawn", &key, &iv, &aad, 12).unwrap();
+        assert_eq!(14 + 12, encrypted.len());
+        let full = crate::gcm::encrypt_aes_gcm(b"attack at dawn", &key, &iv, &aad, 16).unwrap();
+        assert_eq!(full[..26], encrypted[..]);
+        assert_eq!(
+            b"attack at dawn".to_vec(),
+            crate::gcm::decrypt_aes_gcm(&encrypted, &key, &iv, &aad, 12).unwrap()
+        );
+
+        for tag_length in [0, 3, 9, 17] {
+            assert_eq!(
+                Err(CipherError::InvalidTagLength(tag_length)),
+                crate::gcm::encrypt_aes_gcm(b"", &key, &iv, &aad, tag_length)
+            );
+        }
+    }
+
+    #[test]
+    fn tampering_is_rejected() {
+        let key = hex(KEY);
+        let iv = hex("cafebabefacedbaddecaf888");
+        let aad = hex(AAD);
+        let encrypted =
+            crate::gcm::encrypt_aes_gcm(b"attack at dawn", &key, &iv, &aad, 16).unwrap();
+
+        for i in 0..encrypted.len() {
+            let mut tampered = encrypted.clone();
+            tampered[i] ^= 1;
+            assert_eq!(
+                Err(CipherError::AuthenticationFailed),
+                crate::gcm::decrypt_aes_gcm(&tampered, &key, &iv, &aad, 16)
+            );
+        }
+        assert_eq!(
+            Err(CipherError::AuthenticationFailed),
+            crate::gcm::decrypt_aes_gcm(&encrypted, &key, &iv, b"other aad", 16)
+        );
+        assert_eq!(
+            Err(CipherError::AuthenticationFailed),
+            crate::gcm::decrypt_aes_gcm(&encrypted[..10], &key, &iv, &aad, 16)
+        );
+        assert_eq!(
+            Err(CipherError::InvalidIvLength(0)),
+            crate::gcm::decrypt_aes_gcm(&encrypted, &key, &[], &aad, 16)
+        );
+    }
+}
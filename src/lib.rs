@@ -1,5 +1,13 @@
 pub mod aes;
+pub mod cipher;
+pub mod cookie;
+pub mod der;
 pub mod encodings;
+pub mod gcm;
+pub mod modes;
+pub mod padding;
+pub mod pem;
 pub mod set1;
 pub mod set2;
+pub mod set4;
 pub mod util;
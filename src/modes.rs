@@ -0,0 +1,553 @@
+use crate::cipher::{BlockCipher, CipherError};
+use crate::padding::{Padding, PaddingError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endianness {
+    Little,
+    Big,
+}
+
+/// How a CTR counter block is assembled: the nonce followed by the block counter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CtrLayout {
+    pub nonce_length: usize,
+    pub counter_length: usize,
+    pub counter_endianness: Endianness,
+}
+
+impl CtrLayout {
+    /// 64-bit nonce followed by a 64-bit little-endian block counter, as used by cryptopals.
+    pub const CRYPTOPALS: CtrLayout = CtrLayout {
+        nonce_length: 8,
+        counter_length: 8,
+        counter_endianness: Endianness::Little,
+    };
+    /// 96-bit nonce followed by a 32-bit big-endian block counter, as used by GCM and most
+    /// protocols.
+    pub const NONCE_96: CtrLayout = CtrLayout {
+        nonce_length: 12,
+        counter_length: 4,
+        counter_endianness: Endianness::Big,
+    };
+}
+
+impl Default for CtrLayout {
+    fn default() -> Self {
+        CtrLayout::CRYPTOPALS
+    }
+}
+
+fn check_iv_length<C: BlockCipher>(cipher: &C, iv: &[u8]) -> Result<(), CipherError> {
+    if iv.len() != cipher.block_size() {
+        return Err(CipherError::InvalidIvLength(iv.len()));
+    }
+    Ok(())
+}
+
+// Pad `bytes`, making sure the padding scheme really produced whole blocks.
+fn pad<C: BlockCipher>(
+    cipher: &C,
+    padding: &dyn Padding,
+    bytes: &[u8],
+) -> Result<Vec<u8>, CipherError> {
+    let mut padded = bytes.to_vec();
+    padding.pad(&mut padded, cipher.block_size())?;
+    if !padded.len().is_multiple_of(cipher.block_size()) {
+        return Err(PaddingError::NotBlockAligned(padded.len()).into());
+    }
+    Ok(padded)
+}
+
+// Check that `encrypted` is whole blocks, and not empty if `padding` would have added a block.
+fn check_ciphertext_length<C: BlockCipher>(
+    cipher: &C,
+    padding: &dyn Padding,
+    encrypted: &[u8],
+) -> Result<(), CipherError> {
+    let empty = encrypted.is_empty() && padding.always_pads();
+    if empty || !encrypted.len().is_multiple_of(cipher.block_size()) {
+        return Err(CipherError::InvalidCiphertextLength(encrypted.len()));
+    }
+    Ok(())
+}
+
+fn xor_in_place(a: &mut [u8], b: &[u8]) {
+    for (x, y) in a.iter_mut().zip(b) {
+        *x ^= y;
+    }
+}
+
+pub fn ecb_encrypt<C: BlockCipher>(
+    cipher: &C,
+    padding: &dyn Padding,
+    bytes: &[u8],
+) -> Result<Vec<u8>, CipherError> {
+    let block_size = cipher.block_size();
+    let mut encrypted = pad(cipher, padding, bytes)?;
+    for block in encrypted.chunks_mut(block_size) {
+        cipher.encrypt_block(block);
+    }
+    Ok(encrypted)
+}
+
+pub fn ecb_decrypt<C: BlockCipher>(
+    cipher: &C,
+    padding: &dyn Padding,
+    encrypted: &[u8],
+) -> Result<Vec<u8>, CipherError> {
+    check_ciphertext_length(cipher, padding, encrypted)?;
+    let block_size = cipher.block_size();
+    let mut decrypted = encrypted.to_vec();
+    for block in decrypted.chunks_mut(block_size) {
+        cipher.decrypt_block(block);
+    }
+    padding.unpad(&mut decrypted, block_size)?;
+    Ok(decrypted)
+}
+
+// Each plaintext block is XORed with the previous ciphertext block, or the IV for the first,
+// before being encrypted.
+pub fn cbc_encrypt<C: BlockCipher>(
+    cipher: &C,
+    padding: &dyn Padding,
+    iv: &[u8],
+    bytes: &[u8],
+) -> Result<Vec<u8>, CipherError> {
+    check_iv_length(cipher, iv)?;
+    let block_size = cipher.block_size();
+    let mut encrypted = pad(cipher, padding, bytes)?;
+
+    let mut previous_block = iv.to_vec();
+    for block in encrypted.chunks_mut(block_size) {
+        xor_in_place(block, &previous_block);
+        cipher.encrypt_block(block);
+        previous_block.copy_from_slice(block);
+    }
+    Ok(encrypted)
+}
+
+pub fn cbc_decrypt<C: BlockCipher>(
+    cipher: &C,
+    padding: &dyn Padding,
+    iv: &[u8],
+    encrypted: &[u8],
+) -> Result<Vec<u8>, CipherError> {
+    check_iv_length(cipher, iv)?;
+    check_ciphertext_length(cipher, padding, encrypted)?;
+    let block_size = cipher.block_size();
+
+    let mut decrypted = encrypted.to_vec();
+    let previous_blocks = std::iter::once(iv).chain(encrypted.chunks(block_size));
+    for (block, previous_block) in decrypted.chunks_mut(block_size).zip(previous_blocks) {
+        cipher.decrypt_block(block);
+        xor_in_place(block, previous_block);
+    }
+    padding.unpad(&mut decrypted, block_size)?;
+    Ok(decrypted)
+}
+
+/// A CTR keystream, generated one block at a time as it is consumed.
+///
+/// The counter wraps around silently once it overflows `counter_length` bytes.
+pub struct CtrKeystream<C: BlockCipher> {
+    cipher: C,
+    nonce: Vec<u8>,
+    layout: CtrLayout,
+    counter: u128,
+    block: Vec<u8>,
+    position: usize,
+}
+
+impl<C: BlockCipher> CtrKeystream<C> {
+    pub fn new(cipher: C, nonce: &[u8], layout: CtrLayout) -> Result<Self, CipherError> {
+        let block_size = cipher.block_size();
+        // A zero-width counter would encrypt the same block over and over, repeating the keystream.
+        if layout.nonce_length + layout.counter_length != block_size
+            || layout.counter_length == 0
+            || layout.counter_length > 16
+        {
+            return Err(CipherError::InvalidCtrLayout(layout));
+        }
+        if nonce.len() != layout.nonce_length {
+            return Err(CipherError::InvalidIvLength(nonce.len()));
+        }
+
+        Ok(CtrKeystream {
+            cipher,
+            nonce: nonce.to_vec(),
+            layout,
+            counter: 0,
+            block: vec![0; block_size],
+            position: block_size,
+        })
+    }
+
+    // Move to byte `offset` of the keystream, generating only the block that contains it.
+    pub fn seek(&mut self, offset: u64) {
+        let block_size = self.block.len() as u64;
+        self.counter = self.wrap_counter((offset / block_size) as u128);
+        self.next_block();
+        self.position = (offset % block_size) as usize;
+    }
+
+    fn wrap_counter(&self, counter: u128) -> u128 {
+        let modulus_bits = 8 * self.layout.counter_length as u32;
+        if modulus_bits < 128 {
+            counter & ((1 << modulus_bits) - 1)
+        } else {
+            counter
+        }
+    }
+
+    fn counter_block(&self) -> Vec<u8> {
+        let mut block = self.nonce.clone();
+        let width = self.layout.counter_length;
+        match self.layout.counter_endianness {
+            Endianness::Little => block.extend_from_slice(&self.counter.to_le_bytes()[..width]),
+            Endianness::Big => block.extend_from_slice(&self.counter.to_be_bytes()[16 - width..]),
+        }
+        block
+    }
+
+    fn next_block(&mut self) {
+        self.block = self.counter_block();
+        self.cipher.encrypt_block(&mut self.block);
+        self.position = 0;
+        self.counter = self.wrap_counter(self.counter.wrapping_add(1));
+    }
+}
+
+impl<C: BlockCipher> Iterator for CtrKeystream<C> {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<u8> {
+        if self.position == self.block.len() {
+            self.next_block();
+        }
+        let b = self.block[self.position];
+        self.position += 1;
+        Some(b)
+    }
+}
+
+// Encryption and decryption are the same operation in CTR mode.
+pub fn ctr_apply<C: BlockCipher>(
+    cipher: &C,
+    nonce: &[u8],
+    layout: CtrLayout,
+    bytes: &[u8],
+) -> Result<Vec<u8>, CipherError> {
+    Ok(CtrKeystream::new(cipher, nonce, layout)?
+        .zip(bytes)
+        .map(|(k, b)| k ^ b)
+        .collect())
+}
+
+// Full-block cipher feedback: each block of keystream is the encryption of the previous
+// ciphertext block, or the IV for the first. A final partial block is truncated, so no padding
+// is needed.
+pub fn cfb_encrypt<C: BlockCipher>(
+    cipher: &C,
+    iv: &[u8],
+    bytes: &[u8],
+) -> Result<Vec<u8>, CipherError> {
+    check_iv_length(cipher, iv)?;
+    let mut encrypted = bytes.to_vec();
+    let mut feedback = iv.to_vec();
+    for block in encrypted.chunks_mut(cipher.block_size()) {
+        cipher.encrypt_block(&mut feedback);
+        xor_in_place(block, &feedback);
+        feedback[..block.len()].copy_from_slice(block);
+    }
+    Ok(encrypted)
+}
+
+pub fn cfb_decrypt<C: BlockCipher>(
+    cipher: &C,
+    iv: &[u8],
+    encrypted: &[u8],
+) -> Result<Vec<u8>, CipherError> {
+    check_iv_length(cipher, iv)?;
+    let mut decrypted = encrypted.to_vec();
+    let mut feedback = iv.to_vec();
+    for (block, ciphertext) in decrypted
+        .chunks_mut(cipher.block_size())
+        .zip(encrypted.chunks(cipher.block_size()))
+    {
+        cipher.encrypt_block(&mut feedback);
+        xor_in_place(block, &feedback);
+        feedback[..ciphertext.len()].copy_from_slice(ciphertext);
+    }
+    Ok(decrypted)
+}
+
+// 8-bit cipher feedback: the IV is a shift register, and each plaintext byte is XORed with the
+// first byte of the register's encryption before the resulting ciphertext byte is shifted in.
+pub fn cfb8_encrypt<C: BlockCipher>(
+    cipher: &C,
+    iv: &[u8],
+    bytes: &[u8],
+) -> Result<Vec<u8>, CipherError> {
+    check_iv_length(cipher, iv)?;
+    let mut register = iv.to_vec();
+    let mut block = vec![0; iv.len()];
+    Ok(bytes
+        .iter()
+        .map(|b| {
+            block.copy_from_slice(&register);
+            cipher.encrypt_block(&mut block);
+            let c = b ^ block[0];
+            register.rotate_left(1);
+            *register.last_mut().unwrap() = c;
+            c
+        })
+        .collect())
+}
+
+pub fn cfb8_decrypt<C: BlockCipher>(
+    cipher: &C,
+    iv: &[u8],
+    encrypted: &[u8],
+) -> Result<Vec<u8>, CipherError> {
+    check_iv_length(cipher, iv)?;
+    let mut register = iv.to_vec();
+    let mut block = vec![0; iv.len()];
+    Ok(encrypted
+        .iter()
+        .map(|c| {
+            block.copy_from_slice(&register);
+            cipher.encrypt_block(&mut block);
+            register.rotate_left(1);
+            *register.last_mut().unwrap() = *c;
+            c ^ block[0]
+        })
+        .collect())
+}
+
+// Output feedback: the keystream is the IV encrypted repeatedly, independent of the data, so
+// encryption and decryption are the same operation.
+pub fn ofb_apply<C: BlockCipher>(
+    cipher: &C,
+    iv: &[u8],
+    bytes: &[u8],
+) -> Result<Vec<u8>, CipherError> {
+    check_iv_length(cipher, iv)?;
+    let mut output = bytes.to_vec();
+    let mut feedback = iv.to_vec();
+    for block in output.chunks_mut(cipher.block_size()) {
+        cipher.encrypt_block(&mut feedback);
+        xor_in_place(block, &feedback);
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::cipher::{CipherError, XorCipher};
+    use crate::modes::{CtrLayout, Endianness};
+    use crate::padding::{NoPadding, PaddingError, Pkcs7};
+
+    const PLAINTEXT: &[u8] = b"I hope you are having lots of fun in trying to catch me.";
+
+    #[test]
+    fn toy_cipher_ecb_repeats_blocks() {
+        let cipher = XorCipher::new(&[0x55; 8]).unwrap();
+        let encrypted = crate::modes::ecb_encrypt(&cipher, &Pkcs7, &[b'A'; 16]).unwrap();
+        assert_eq!(24, encrypted.len());
+        assert_eq!(encrypted[..8], encrypted[8..16]);
+        assert_eq!(
+            [b'A'; 16].to_vec(),
+            crate::modes::ecb_decrypt(&cipher, &Pkcs7, &encrypted).unwrap()
+        );
+    }
+
+    #[test]
+    fn toy_cipher_cbc_chains_blocks() {
+        let cipher = XorCipher::new(&[0; 4]).unwrap();
+        let iv = [1, 2, 3, 4];
+        // With an identity cipher, each ciphertext block is the running XOR of the plaintext
+        // blocks and the IV.
+        let encrypted =
+            crate::modes::cbc_encrypt(&cipher, &NoPadding, &iv, &[0, 0, 0, 0, 1, 1, 1, 1]).unwrap();
+        assert_eq!(vec![1, 2, 3, 4, 0, 3, 2, 5], encrypted);
+        assert_eq!(
+            vec![0, 0, 0, 0, 1, 1, 1, 1],
+            crate::modes::cbc_decrypt(&cipher, &NoPadding, &iv, &encrypted).unwrap()
+        );
+        assert_eq!(
+            Err(CipherError::Padding(PaddingError::NotBlockAligned(5))),
+            crate::modes::cbc_encrypt(&cipher, &NoPadding, &iv, &[0; 5])
+        );
+    }
+
+    #[test]
+    fn invalid_arguments_are_errors() {
+        assert_eq!(Err(CipherError::InvalidKeyLength(0)), XorCipher::new(&[]));
+
+        let cipher = XorCipher::new(&[0x55; 8]).unwrap();
+        assert_eq!(
+            Err(CipherError::InvalidIvLength(7)),
+            crate::modes::cbc_encrypt(&cipher, &Pkcs7, &[0; 7], PLAINTEXT)
+        );
+        assert_eq!(
+            Err(CipherError::InvalidIvLength(0)),
+            crate::modes::ofb_apply(&cipher, &[], PLAINTEXT)
+        );
+        assert_eq!(
+            Err(CipherError::InvalidCiphertextLength(9)),
+            crate::modes::ecb_decrypt(&cipher, &Pkcs7, &[0; 9])
+        );
+        assert_eq!(
+            Err(CipherError::InvalidCiphertextLength(12)),
+            crate::modes::cbc_decrypt(&cipher, &Pkcs7, &[0; 8], &[0; 12])
+        );
+        assert_eq!(
+            Err(CipherError::InvalidCiphertextLength(0)),
+            crate::modes::cbc_decrypt(&cipher, &Pkcs7, &[0; 8], &[])
+        );
+
+        let layout = CtrLayout::CRYPTOPALS;
+        assert_eq!(
+            Err(CipherError::InvalidCtrLayout(layout)),
+            crate::modes::ctr_apply(&cipher, &[0; 8], layout, PLAINTEXT)
+        );
+        let layout = CtrLayout {
+            nonce_length: 4,
+            counter_length: 4,
+            counter_endianness: Endianness::Big,
+        };
+        assert_eq!(
+            Err(CipherError::InvalidIvLength(3)),
+            crate::modes::ctr_apply(&cipher, &[0; 3], layout, PLAINTEXT)
+        );
+        let layout = CtrLayout {
+            nonce_length: 8,
+            counter_length: 0,
+            counter_endianness: Endianness::Big,
+        };
+        assert_eq!(
+            Err(CipherError::InvalidCtrLayout(layout)),
+            crate::modes::ctr_apply(&cipher, &[0; 8], layout, PLAINTEXT)
+        );
+    }
+
+    #[test]
+    fn empty_input_with_each_padding() {
+        use crate::padding::{AnsiX923, Iso10126, Iso7816, Padding, Pkcs7Lenient, ZeroPadding};
+
+        let cipher = XorCipher::new(&[0x55; 8]).unwrap();
+        let iv = [9; 8];
+        let schemes: [&dyn Padding; 7] = [
+            &Pkcs7,
+            &Pkcs7Lenient,
+            &AnsiX923,
+            &Iso7816,
+            &Iso10126,
+            &ZeroPadding,
+            &NoPadding,
+        ];
+        for padding in schemes {
+            let encrypted = crate::modes::ecb_encrypt(&cipher, padding, &[]).unwrap();
+            assert_eq!(padding.always_pads(), !encrypted.is_empty());
+            assert_eq!(
+                Vec::<u8>::new(),
+                crate::modes::ecb_decrypt(&cipher, padding, &encrypted).unwrap()
+            );
+
+            let encrypted = crate::modes::cbc_encrypt(&cipher, padding, &iv, &[]).unwrap();
+            assert_eq!(padding.always_pads(), !encrypted.is_empty());
+            assert_eq!(
+                Vec::<u8>::new(),
+                crate::modes::cbc_decrypt(&cipher, padding, &iv, &encrypted).unwrap()
+            );
+
+            if padding.always_pads() {
+                assert_eq!(
+                    Err(CipherError::InvalidCiphertextLength(0)),
+                    crate::modes::ecb_decrypt(&cipher, padding, &[])
+                );
+                assert_eq!(
+                    Err(CipherError::InvalidCiphertextLength(0)),
+                    crate::modes::cbc_decrypt(&cipher, padding, &iv, &[])
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn toy_cipher_round_trips() {
+        let cipher = XorCipher::new(b"8 bytes!").unwrap();
+        let iv = [9; 8];
+        let layout = CtrLayout {
+            nonce_length: 4,
+            counter_length: 4,
+            counter_endianness: Endianness::Big,
+        };
+
+        let encrypted = crate::modes::cbc_encrypt(&cipher, &Pkcs7, &iv, PLAINTEXT).unwrap();
+        assert_eq!(
+            PLAINTEXT,
+            crate::modes::cbc_decrypt(&cipher, &Pkcs7, &iv, &encrypted).unwrap()
+        );
+
+        let encrypted = crate::modes::ctr_apply(&cipher, &[7; 4], layout, PLAINTEXT).unwrap();
+        assert_eq!(PLAINTEXT.len(), encrypted.len());
+        assert_eq!(
+            PLAINTEXT,
+            crate::modes::ctr_apply(&cipher, &[7; 4], layout, &encrypted).unwrap()
+        );
+
+        let encrypted = crate::modes::cfb_encrypt(&cipher, &iv, PLAINTEXT).unwrap();
+        assert_eq!(PLAINTEXT.len(), encrypted.len());
+        assert_eq!(
+            PLAINTEXT,
+            crate::modes::cfb_decrypt(&cipher, &iv, &encrypted).unwrap()
+        );
+
+        let encrypted = crate::modes::cfb8_encrypt(&cipher, &iv, PLAINTEXT).unwrap();
+        assert_eq!(PLAINTEXT.len(), encrypted.len());
+        assert_eq!(
+            PLAINTEXT,
+            crate::modes::cfb8_decrypt(&cipher, &iv, &encrypted).unwrap()
+        );
+
+        let encrypted = crate::modes::ofb_apply(&cipher, &iv, PLAINTEXT).unwrap();
+        assert_eq!(PLAINTEXT.len(), encrypted.len());
+        assert_eq!(
+            PLAINTEXT,
+            crate::modes::ofb_apply(&cipher, &iv, &encrypted).unwrap()
+        );
+    }
+
+    #[test]
+    fn toy_cipher_ctr_counter_blocks() {
+        let cipher = XorCipher::new(&[0; 4]).unwrap();
+        let layout = CtrLayout {
+            nonce_length: 2,
+            counter_length: 2,
+            counter_endianness: Endianness::Little,
+        };
+        let keystream: Vec<u8> = crate::modes::CtrKeystream::new(&cipher, &[0xaa, 0xbb], layout)
+            .unwrap()
+            .take(12)
+            .collect();
+        assert_eq!(
+            vec![0xaa, 0xbb, 0, 0, 0xaa, 0xbb, 1, 0, 0xaa, 0xbb, 2, 0],
+            keystream
+        );
+    }
+
+    #[test]
+    fn aes_modes_match_aes_functions() {
+        let key = "YELLOW SUBMARINE".as_bytes();
+        let cipher = crate::aes::Aes::new(key).unwrap();
+        assert_eq!(
+            crate::aes::encrypt_aes128_ecb(PLAINTEXT, key).unwrap(),
+            crate::modes::ecb_encrypt(&cipher, &Pkcs7, PLAINTEXT).unwrap()
+        );
+        assert_eq!(
+            crate::aes::encrypt_aes128_cbc(PLAINTEXT, key, &[3; 16]).unwrap(),
+            crate::modes::cbc_encrypt(&cipher, &Pkcs7, &[3; 16], PLAINTEXT).unwrap()
+        );
+    }
+}
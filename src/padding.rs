@@ -0,0 +1,409 @@
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PaddingError {
+    /// Data whose length isn't a whole number of blocks, or is empty where a padding block is
+    /// required.
+    NotBlockAligned(usize),
+    /// A padding length byte that is zero or larger than the block size.
+    InvalidPaddingLength(u8),
+    /// A padding byte that doesn't have the value the scheme requires.
+    InvalidPaddingByte { offset: usize },
+    /// ISO/IEC 7816-4 padding without its 0x80 marker in the final block.
+    MissingMarker,
+    /// A block size of zero, or one too large for the scheme to record in its length byte.
+    UnsupportedBlockSize(usize),
+}
+
+impl fmt::Display for PaddingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PaddingError::NotBlockAligned(len) => {
+                write!(f, "length {} is not a whole number of blocks", len)
+            }
+            PaddingError::InvalidPaddingLength(len) => {
+                write!(f, "invalid padding length {}", len)
+            }
+            PaddingError::InvalidPaddingByte { offset } => {
+                write!(f, "invalid padding byte at offset {}", offset)
+            }
+            PaddingError::MissingMarker => write!(f, "missing padding marker"),
+            PaddingError::UnsupportedBlockSize(size) => {
+                write!(f, "unsupported block size {}", size)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PaddingError {}
+
+/// A block padding scheme, used by the block modes that need whole blocks.
+pub trait Padding {
+    // Pad `bytes` to a whole number of blocks.
+    fn pad(&self, bytes: &mut Vec<u8>, block_size: usize) -> Result<(), PaddingError>;
+
+    // Remove the padding added by `pad`.
+    fn unpad(&self, bytes: &mut Vec<u8>, block_size: usize) -> Result<(), PaddingError>;
+
+    // Whether `pad` always adds at least one byte, so that padded data is never empty.
+    fn always_pads(&self) -> bool {
+        true
+    }
+}
+
+// Check that `block_size` is non-zero and no larger than `max`, which is 255 for schemes that
+// store the padding length in a byte.
+fn check_block_size(block_size: usize, max: usize) -> Result<(), PaddingError> {
+    if block_size == 0 || block_size > max {
+        return Err(PaddingError::UnsupportedBlockSize(block_size));
+    }
+    Ok(())
+}
+
+fn padding_length(bytes: &[u8], block_size: usize) -> usize {
+    block_size - bytes.len() % block_size
+}
+
+fn check_aligned(bytes: &[u8], block_size: usize) -> Result<(), PaddingError> {
+    if bytes.is_empty() || !bytes.len().is_multiple_of(block_size) {
+        return Err(PaddingError::NotBlockAligned(bytes.len()));
+    }
+    Ok(())
+}
+
+// Read the trailing length byte used by PKCS #7, ANSI X.923 and ISO 10126.
+fn trailing_length(bytes: &[u8], block_size: usize) -> Result<usize, PaddingError> {
+    check_aligned(bytes, block_size)?;
+    let len = bytes[bytes.len() - 1];
+    if len == 0 || len as usize > block_size {
+        return Err(PaddingError::InvalidPaddingLength(len));
+    }
+    Ok(len as usize)
+}
+
+/// PKCS #7: every padding byte holds the number of padding bytes. A full block is added to
+/// block-aligned input, including empty input.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Pkcs7;
+
+impl Padding for Pkcs7 {
+    fn pad(&self, bytes: &mut Vec<u8>, block_size: usize) -> Result<(), PaddingError> {
+        check_block_size(block_size, u8::MAX as usize)?;
+        let len = padding_length(bytes, block_size);
+        bytes.resize(bytes.len() + len, len as u8);
+        Ok(())
+    }
+
+    fn unpad(&self, bytes: &mut Vec<u8>, block_size: usize) -> Result<(), PaddingError> {
+        check_block_size(block_size, u8::MAX as usize)?;
+        crate::util::pkcs7_unpad_strict(bytes, block_size as u8)
+    }
+}
+
+/// PKCS #7 padding that is removed without any validation, as `util::pkcs7_unpad` does, for
+/// reproducing servers that accept malformed padding.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Pkcs7Lenient;
+
+impl Padding for Pkcs7Lenient {
+    fn pad(&self, bytes: &mut Vec<u8>, block_size: usize) -> Result<(), PaddingError> {
+        Pkcs7.pad(bytes, block_size)
+    }
+
+    fn unpad(&self, bytes: &mut Vec<u8>, _block_size: usize) -> Result<(), PaddingError> {
+        crate::util::pkcs7_unpad(bytes);
+        Ok(())
+    }
+}
+
+/// ANSI X.923: zero bytes followed by a final byte holding the number of padding bytes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AnsiX923;
+
+impl Padding for AnsiX923 {
+    fn pad(&self, bytes: &mut Vec<u8>, block_size: usize) -> Result<(), PaddingError> {
+        check_block_size(block_size, u8::MAX as usize)?;
+        let len = padding_length(bytes, block_size);
+        bytes.resize(bytes.len() + len - 1, 0);
+        bytes.push(len as u8);
+        Ok(())
+    }
+
+    fn unpad(&self, bytes: &mut Vec<u8>, block_size: usize) -> Result<(), PaddingError> {
+        check_block_size(block_size, u8::MAX as usize)?;
+        let len = trailing_length(bytes, block_size)?;
+        let start = bytes.len() - len;
+        if let Some(i) = bytes[start..bytes.len() - 1].iter().position(|b| *b != 0) {
+            return Err(PaddingError::InvalidPaddingByte { offset: start + i });
+        }
+        bytes.truncate(start);
+        Ok(())
+    }
+}
+
+/// ISO/IEC 7816-4: a single 0x80 byte followed by zero bytes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Iso7816;
+
+impl Padding for Iso7816 {
+    fn pad(&self, bytes: &mut Vec<u8>, block_size: usize) -> Result<(), PaddingError> {
+        check_block_size(block_size, usize::MAX)?;
+        let len = padding_length(bytes, block_size);
+        bytes.push(0x80);
+        bytes.resize(bytes.len() + len - 1, 0);
+        Ok(())
+    }
+
+    fn unpad(&self, bytes: &mut Vec<u8>, block_size: usize) -> Result<(), PaddingError> {
+        check_block_size(block_size, usize::MAX)?;
+        check_aligned(bytes, block_size)?;
+        let last_block = bytes.len() - block_size;
+        match bytes[last_block..].iter().rposition(|b| *b != 0) {
+            Some(i) if bytes[last_block + i] == 0x80 => {
+                bytes.truncate(last_block + i);
+                Ok(())
+            }
+            Some(i) => Err(PaddingError::InvalidPaddingByte {
+                offset: last_block + i,
+            }),
+            None => Err(PaddingError::MissingMarker),
+        }
+    }
+}
+
+/// ISO 10126: random bytes followed by a final byte holding the number of padding bytes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Iso10126;
+
+impl Padding for Iso10126 {
+    fn pad(&self, bytes: &mut Vec<u8>, block_size: usize) -> Result<(), PaddingError> {
+        check_block_size(block_size, u8::MAX as usize)?;
+        let len = padding_length(bytes, block_size);
+        bytes.extend((1..len).map(|_| rand::random::<u8>()));
+        bytes.push(len as u8);
+        Ok(())
+    }
+
+    // The random bytes can't be checked, only the length.
+    fn unpad(&self, bytes: &mut Vec<u8>, block_size: usize) -> Result<(), PaddingError> {
+        check_block_size(block_size, u8::MAX as usize)?;
+        let len = trailing_length(bytes, block_size)?;
+        bytes.truncate(bytes.len() - len);
+        Ok(())
+    }
+}
+
+/// Zero bytes up to the next block boundary, with nothing added to block-aligned input. Removing
+/// it also strips any trailing zero bytes of the data itself.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ZeroPadding;
+
+impl Padding for ZeroPadding {
+    fn always_pads(&self) -> bool {
+        false
+    }
+
+    fn pad(&self, bytes: &mut Vec<u8>, block_size: usize) -> Result<(), PaddingError> {
+        check_block_size(block_size, usize::MAX)?;
+        let len = padding_length(bytes, block_size) % block_size;
+        bytes.resize(bytes.len() + len, 0);
+        Ok(())
+    }
+
+    fn unpad(&self, bytes: &mut Vec<u8>, block_size: usize) -> Result<(), PaddingError> {
+        check_block_size(block_size, usize::MAX)?;
+        if !bytes.len().is_multiple_of(block_size) {
+            return Err(PaddingError::NotBlockAligned(bytes.len()));
+        }
+        let last_block = bytes.len().saturating_sub(block_size);
+        let zeros = bytes[last_block..]
+            .iter()
+            .rev()
+            .take_while(|b| **b == 0)
+            .count();
+        bytes.truncate(bytes.len() - zeros);
+        Ok(())
+    }
+}
+
+/// No padding: the data must already be a whole number of blocks.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NoPadding;
+
+impl Padding for NoPadding {
+    fn always_pads(&self) -> bool {
+        false
+    }
+
+    fn pad(&self, bytes: &mut Vec<u8>, block_size: usize) -> Result<(), PaddingError> {
+        self.unpad(bytes, block_size)
+    }
+
+    fn unpad(&self, bytes: &mut Vec<u8>, block_size: usize) -> Result<(), PaddingError> {
+        check_block_size(block_size, usize::MAX)?;
+        if !bytes.len().is_multiple_of(block_size) {
+            return Err(PaddingError::NotBlockAligned(bytes.len()));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::padding::{
+        AnsiX923, Iso10126, Iso7816, NoPadding, Padding, PaddingError, Pkcs7, Pkcs7Lenient,
+        ZeroPadding,
+    };
+
+    fn padded(padding: &dyn Padding, bytes: &[u8], block_size: usize) -> Vec<u8> {
+        let mut bytes = bytes.to_vec();
+        padding.pad(&mut bytes, block_size).unwrap();
+        bytes
+    }
+
+    fn unpadded(
+        padding: &dyn Padding,
+        bytes: &[u8],
+        block_size: usize,
+    ) -> Result<Vec<u8>, PaddingError> {
+        let mut bytes = bytes.to_vec();
+        padding.unpad(&mut bytes, block_size)?;
+        Ok(bytes)
+    }
+
+    #[test]
+    fn known_paddings() {
+        let data = [0xdd; 5];
+        assert_eq!(
+            vec![0xdd, 0xdd, 0xdd, 0xdd, 0xdd, 3, 3, 3],
+            padded(&Pkcs7, &data, 8)
+        );
+        assert_eq!(
+            vec![0xdd, 0xdd, 0xdd, 0xdd, 0xdd, 0, 0, 3],
+            padded(&AnsiX923, &data, 8)
+        );
+        assert_eq!(
+            vec![0xdd, 0xdd, 0xdd, 0xdd, 0xdd, 0x80, 0, 0],
+            padded(&Iso7816, &data, 8)
+        );
+        assert_eq!(
+            vec![0xdd, 0xdd, 0xdd, 0xdd, 0xdd, 0, 0, 0],
+            padded(&ZeroPadding, &data, 8)
+        );
+        let iso10126 = padded(&Iso10126, &data, 8);
+        assert_eq!(8, iso10126.len());
+        assert_eq!(3, iso10126[7]);
+
+        assert_eq!(vec![4; 4], padded(&Pkcs7, &[], 4));
+        assert_eq!(Vec::<u8>::new(), padded(&ZeroPadding, &[], 4));
+        assert_eq!(vec![1, 2, 3, 4], padded(&NoPadding, &[1, 2, 3, 4], 4));
+    }
+
+    #[test]
+    fn round_trips() {
+        let schemes: [&dyn Padding; 6] = [
+            &Pkcs7,
+            &Pkcs7Lenient,
+            &AnsiX923,
+            &Iso7816,
+            &Iso10126,
+            &NoPadding,
+        ];
+        for block_size in [8, 16] {
+            for len in 0..=2 * block_size + 1 {
+                let data: Vec<u8> = (1..=len as u8).collect();
+                for padding in schemes {
+                    let mut bytes = data.clone();
+                    if padding.pad(&mut bytes, block_size).is_err() {
+                        assert!(!len.is_multiple_of(block_size));
+                        continue;
+                    }
+                    assert!(bytes.len().is_multiple_of(block_size));
+                    assert_eq!(data, unpadded(padding, &bytes, block_size).unwrap());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn zero_padding_strips_trailing_zeros() {
+        let bytes = padded(&ZeroPadding, &[1, 2, 0], 4);
+        assert_eq!(vec![1, 2, 0, 0], bytes);
+        assert_eq!(vec![1, 2], unpadded(&ZeroPadding, &bytes, 4).unwrap());
+    }
+
+    #[test]
+    fn invalid_paddings() {
+        assert_eq!(
+            Err(PaddingError::NotBlockAligned(0)),
+            unpadded(&Pkcs7, &[], 4)
+        );
+        assert_eq!(
+            Err(PaddingError::InvalidPaddingByte { offset: 2 }),
+            unpadded(&Pkcs7, &[1, 2, 1, 2], 4)
+        );
+        // The lenient scheme accepts the same data.
+        assert_eq!(
+            vec![1, 2],
+            unpadded(&Pkcs7Lenient, &[1, 2, 1, 2], 4).unwrap()
+        );
+        assert_eq!(
+            Err(PaddingError::NotBlockAligned(3)),
+            unpadded(&AnsiX923, &[1, 2, 1], 4)
+        );
+        assert_eq!(
+            Err(PaddingError::InvalidPaddingLength(5)),
+            unpadded(&Iso10126, &[1, 2, 3, 5], 4)
+        );
+        assert_eq!(
+            Err(PaddingError::InvalidPaddingByte { offset: 1 }),
+            unpadded(&AnsiX923, &[1, 2, 0, 3], 4)
+        );
+        assert_eq!(
+            Err(PaddingError::InvalidPaddingByte { offset: 2 }),
+            unpadded(&Iso7816, &[1, 0x80, 1, 0], 4)
+        );
+        assert_eq!(
+            Err(PaddingError::MissingMarker),
+            unpadded(&Iso7816, &[1, 2, 3, 4, 0, 0, 0, 0], 4)
+        );
+        assert_eq!(
+            Err(PaddingError::NotBlockAligned(5)),
+            unpadded(&NoPadding, &[1, 2, 3, 4, 5], 4)
+        );
+    }
+
+    #[test]
+    fn unsupported_block_sizes() {
+        let schemes: [&dyn Padding; 7] = [
+            &Pkcs7,
+            &Pkcs7Lenient,
+            &AnsiX923,
+            &Iso7816,
+            &Iso10126,
+            &ZeroPadding,
+            &NoPadding,
+        ];
+        for padding in schemes {
+            let mut bytes = vec![1, 2, 3];
+            assert_eq!(
+                Err(PaddingError::UnsupportedBlockSize(0)),
+                padding.pad(&mut bytes, 0)
+            );
+        }
+
+        let mut bytes = vec![1, 2, 3];
+        for padding in [&Pkcs7 as &dyn Padding, &AnsiX923, &Iso10126] {
+            assert_eq!(
+                Err(PaddingError::UnsupportedBlockSize(256)),
+                padding.pad(&mut bytes, 256)
+            );
+            assert_eq!(
+                Err(PaddingError::UnsupportedBlockSize(256)),
+                padding.unpad(&mut bytes, 256)
+            );
+        }
+        assert_eq!(vec![1, 2, 3], bytes);
+        assert_eq!(258, padded(&Iso7816, &[1, 2], 258).len());
+    }
+}
@@ -1,5 +1,6 @@
 use std::collections::HashMap;
 
+use crate::encodings::HexError;
 use crate::util::hamming_distance;
 
 // TODO: Get rid of this and just have functions return the key. This doesn't really scale for
@@ -40,9 +41,19 @@ pub fn english_rating(frequencies: &HashMap<char, f32>, s: &str) -> f32 {
     coefficient
 }
 
-// TODO: Separate out the hex decoding and decrypting
-pub fn detect_single_byte_xor_key(hex: &str) -> Option<Candidate> {
-    let bytes = crate::encodings::hex_decode(hex);
+pub fn detect_single_byte_xor_key(hex: &str) -> Result<Option<Candidate>, HexError> {
+    let bytes = crate::encodings::hex_decode(hex)?;
+    Ok(
+        detect_single_byte_xor_key_bytes(&bytes).map(|candidate| Candidate {
+            encrypted: hex.to_string(),
+            ..candidate
+        }),
+    )
+}
+
+// Find the most English-looking single-byte XOR of the specified bytes. The returned candidate's
+// `encrypted` field is left empty.
+fn detect_single_byte_xor_key_bytes(bytes: &[u8]) -> Option<Candidate> {
     let mut candidates: Vec<Candidate> = Vec::new();
 
     #[allow(clippy::approx_constant)]
@@ -81,13 +92,13 @@ pub fn detect_single_byte_xor_key(hex: &str) -> Option<Candidate> {
             continue;
         }
         // TODO: Separate out the xoring function
-        let xored = crate::util::xor_vec(&bytes, key);
+        let xored = crate::util::xor_vec(bytes, key);
         if let Ok(s) = std::str::from_utf8(&xored) {
             let rating = english_rating(&frequencies, s);
             candidates.push(Candidate {
                 rating,
                 key,
-                encrypted: hex.to_string(),
+                encrypted: String::new(),
                 plaintext: s.to_string(),
             });
         }
@@ -97,17 +108,22 @@ pub fn detect_single_byte_xor_key(hex: &str) -> Option<Candidate> {
     candidates.last().cloned()
 }
 
-pub fn find_xored_string(strings: &Vec<&str>) -> Option<Candidate> {
+// Find the string most likely to have been XORed with a single byte. Lines that aren't valid hex
+// are skipped and returned alongside the best candidate with their index and decoding error.
+pub fn find_xored_string(strings: &[&str]) -> (Option<Candidate>, Vec<(usize, HexError)>) {
     let mut candidates: Vec<Candidate> = Vec::new();
+    let mut errors: Vec<(usize, HexError)> = Vec::new();
 
-    for s in strings {
-        if let Some(candidate) = detect_single_byte_xor_key(*s) {
-            candidates.push(candidate);
+    for (i, s) in strings.iter().enumerate() {
+        match detect_single_byte_xor_key(s) {
+            Ok(Some(candidate)) => candidates.push(candidate),
+            Ok(None) => {}
+            Err(e) => errors.push((i, e)),
         }
     }
 
     candidates.sort_by(|a, b| a.rating.partial_cmp(&b.rating).unwrap());
-    candidates.last().cloned()
+    (candidates.last().cloned(), errors)
 }
 
 // TODO: Have this calculate the average distance using every block?
@@ -120,16 +136,17 @@ fn find_key_size(encrypted: &[u8]) -> u8 {
         let block3 = blocks.next().unwrap();
         let block4 = blocks.next().unwrap();
 
-        let mut normalizd_distances: Vec<f64> = Vec::new();
-        normalizd_distances.push(hamming_distance(&block1, &block2) as f64 / key_size as f64);
-        normalizd_distances.push(hamming_distance(&block1, &block3) as f64 / key_size as f64);
-        normalizd_distances.push(hamming_distance(&block1, &block4) as f64 / key_size as f64);
-        normalizd_distances.push(hamming_distance(&block2, &block3) as f64 / key_size as f64);
-        normalizd_distances.push(hamming_distance(&block2, &block4) as f64 / key_size as f64);
-        normalizd_distances.push(hamming_distance(&block3, &block4) as f64 / key_size as f64);
+        let normalizd_distances: Vec<f64> = vec![
+            hamming_distance(block1, block2) as f64 / key_size as f64,
+            hamming_distance(block1, block3) as f64 / key_size as f64,
+            hamming_distance(block1, block4) as f64 / key_size as f64,
+            hamming_distance(block2, block3) as f64 / key_size as f64,
+            hamming_distance(block2, block4) as f64 / key_size as f64,
+            hamming_distance(block3, block4) as f64 / key_size as f64,
+        ];
 
         let mut average_distance: f64 = normalizd_distances.iter().sum();
-        average_distance = average_distance / normalizd_distances.len() as f64;
+        average_distance /= normalizd_distances.len() as f64;
         if let Some((_, distance)) = key_size_distance {
             if average_distance < distance {
                 key_size_distance = Some((key_size as u8, average_distance));
@@ -147,8 +164,7 @@ pub fn find_repeating_key_xored_string(encrypted: &[u8]) -> String {
     let transposed = crate::util::transpose(encrypted, key_size as usize);
     let mut key = String::new();
     for block in transposed {
-        let hex = crate::encodings::hex_encode(&block);
-        if let Some(best_candidate) = detect_single_byte_xor_key(&hex) {
+        if let Some(best_candidate) = detect_single_byte_xor_key_bytes(&block) {
             key.push(best_candidate.key as char);
         }
     }
@@ -160,7 +176,7 @@ mod test {
     #[test]
     fn challenge1() {
         let hex = "49276d206b696c6c696e6720796f757220627261696e206c696b65206120706f69736f6e6f7573206d757368726f6f6d";
-        let bytes = crate::encodings::hex_decode(&hex);
+        let bytes = crate::encodings::hex_decode(hex).unwrap();
         let base64 = crate::encodings::base64_encode(&bytes);
         assert_eq!(
             base64,
@@ -173,8 +189,8 @@ mod test {
         let a = "1c0111001f010100061a024b53535009181c";
         let b = "686974207468652062756c6c277320657965";
         let xored = crate::util::xor_buffers(
-            &crate::encodings::hex_decode(a),
-            &crate::encodings::hex_decode(b),
+            &crate::encodings::hex_decode(a).unwrap(),
+            &crate::encodings::hex_decode(b).unwrap(),
         );
         assert_eq!(
             "746865206b696420646f6e277420706c6179",
@@ -185,7 +201,9 @@ mod test {
     #[test]
     fn challenge3() {
         let hex = "1b37373331363f78151b7f2b783431333d78397828372d363c78373e783a393b3736";
-        let best_candidate = crate::set1::detect_single_byte_xor_key(&hex).unwrap();
+        let best_candidate = crate::set1::detect_single_byte_xor_key(hex)
+            .unwrap()
+            .unwrap();
         assert_eq!(0x58, best_candidate.key);
         assert_eq!(
             "Cooking MC's like a pound of bacon",
@@ -198,11 +216,42 @@ mod test {
         let file_contents =
             std::fs::read_to_string("data/4.txt").expect("Failed to read XORed strings");
         let xored_strings = file_contents.split_whitespace().collect::<Vec<&str>>();
-        let best_candidate = crate::set1::find_xored_string(&xored_strings).unwrap();
+        let (best_candidate, errors) = crate::set1::find_xored_string(&xored_strings);
+        assert!(errors.is_empty());
+        let best_candidate = best_candidate.unwrap();
         assert_eq!(0x35, best_candidate.key);
         assert_eq!("Now that the party is jumping\n", best_candidate.plaintext);
     }
 
+    #[test]
+    fn find_xored_string_skips_malformed_lines() {
+        let file_contents =
+            std::fs::read_to_string("data/4.txt").expect("Failed to read XORed strings");
+        let mut xored_strings = file_contents.split_whitespace().collect::<Vec<&str>>();
+        xored_strings.insert(0, "not hex");
+        xored_strings.insert(2, "abc");
+        let (best_candidate, errors) = crate::set1::find_xored_string(&xored_strings);
+        assert_eq!(
+            vec![
+                (
+                    0,
+                    crate::encodings::HexError::InvalidDigit {
+                        offset: 0,
+                        character: 'n'
+                    }
+                ),
+                (2, crate::encodings::HexError::OddLength(3)),
+            ],
+            errors
+        );
+        let best_candidate = best_candidate.unwrap();
+        assert_eq!(0x35, best_candidate.key);
+        assert_eq!(
+            "7b5a4215415d544115415d5015455447414c155c46155f4058455c5b523f",
+            best_candidate.encrypted
+        );
+    }
+
     #[test]
     fn challenge5() {
         let plaintext =
@@ -224,7 +273,8 @@ mod test {
     fn challenge7() {
         let base64 = std::fs::read_to_string("data/7.txt").unwrap();
         let bytes = crate::encodings::base64_decode(&base64).unwrap();
-        let decrypted = crate::aes::decrypt_aes128_ecb(&bytes, "YELLOW SUBMARINE".as_bytes());
+        let decrypted =
+            crate::aes::decrypt_aes128_ecb(&bytes, "YELLOW SUBMARINE".as_bytes()).unwrap();
         let plaintext = std::str::from_utf8(&decrypted).unwrap();
         assert!(plaintext.starts_with("I'm back and I'm ringin' the bell"));
     }
@@ -233,6 +283,6 @@ mod test {
     fn challenge8() {
         let read_to_string = std::fs::read_to_string("data/8.txt").unwrap();
         let lines: Vec<&str> = read_to_string.lines().collect();
-        assert_eq!("d880619740a8a19b7840a8a31c810a3d08649af70dc06f4fd5d2d69c744cd283e2dd052f6b641dbf9d11b0348542bb5708649af70dc06f4fd5d2d69c744cd2839475c9dfdbc1d46597949d9c7e82bf5a08649af70dc06f4fd5d2d69c744cd28397a93eab8d6aecd566489154789a6b0308649af70dc06f4fd5d2d69c744cd283d403180c98c8f6db1f2a3f9c4040deb0ab51b29933f2c123c58386b06fba186a", crate::aes::detect_aes128_ecb(&lines));
+        assert_eq!("d880619740a8a19b7840a8a31c810a3d08649af70dc06f4fd5d2d69c744cd283e2dd052f6b641dbf9d11b0348542bb5708649af70dc06f4fd5d2d69c744cd2839475c9dfdbc1d46597949d9c7e82bf5a08649af70dc06f4fd5d2d69c744cd28397a93eab8d6aecd566489154789a6b0308649af70dc06f4fd5d2d69c744cd283d403180c98c8f6db1f2a3f9c4040deb0ab51b29933f2c123c58386b06fba186a", crate::aes::detect_aes128_ecb(&lines).unwrap().unwrap());
     }
 }
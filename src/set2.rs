@@ -1,3 +1,405 @@
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::fmt;
+
+use rand::Rng;
+
+use crate::aes::{decrypt_aes128_ecb, encrypt_aes128_ecb, CipherError};
+use crate::cookie::{Cookie, CookieError, Mode, Separator};
+use crate::encodings::Base64Error;
+use crate::padding::{Padding, Pkcs7};
+use crate::util::{random_key, CipherMode};
+
+/// The secret appended by the cryptopals challenge 12 oracle, Base64-encoded.
+pub const CHALLENGE12_SECRET: &str = "Um9sbGluJyBpbiBteSA1LjAKV2l0aCBteSByYWctdG9wIGRvd24gc28gbXkg\
+    aGFpciBjYW4gYmxvdwpUaGUgZ2lybGllcyBvbiBzdGFuZGJ5IHdhdmluZyBqdXN0IHRvIHNheSBoaQpEaWQgeW91IHN0\
+    b3A/IE5vLCBJIGp1c3QgZHJvdmUgYnkK";
+
+// Classify an encryption oracle as ECB or CBC from a single chosen plaintext. Three blocks of
+// identical bytes contain two whole identical blocks for any prefix up to 16 bytes long, and
+// only ECB encrypts those to identical ciphertext blocks.
+pub fn detect_block_mode<F: Fn(&[u8]) -> Vec<u8>>(oracle: F) -> CipherMode {
+    let encrypted = oracle(&[0; 48]);
+    if crate::aes::repeated_blocks(&encrypted, 16) > 0 {
+        CipherMode::Ecb
+    } else {
+        CipherMode::Cbc
+    }
+}
+
+/// An ECB oracle that appends a secret suffix to the caller's input and encrypts it under a
+/// fixed random key.
+pub struct SuffixOracle {
+    key: [u8; 16],
+    suffix: Vec<u8>,
+}
+
+impl SuffixOracle {
+    pub fn new(suffix: &[u8]) -> Self {
+        SuffixOracle {
+            key: random_key(),
+            suffix: suffix.to_vec(),
+        }
+    }
+
+    pub fn from_base64(suffix: &str) -> Result<Self, Base64Error> {
+        Ok(Self::new(&crate::encodings::base64_decode(suffix)?))
+    }
+
+    pub fn encrypt(&self, bytes: &[u8]) -> Vec<u8> {
+        let mut plaintext = bytes.to_vec();
+        plaintext.extend_from_slice(&self.suffix);
+        encrypt_aes128_ecb(&plaintext, &self.key).expect("oracle key is 16 bytes")
+    }
+}
+
+// Longest input `discover_block_size` tries before giving up on the ciphertext growing.
+const MAX_BLOCK_SIZE: usize = 256;
+
+// Find the block size and the length of the data the oracle adds, by growing the input until the
+// ciphertext gains a block. With `n` bytes of input the padding was exactly one full block, so
+// the added data is `n` bytes shorter than the previous ciphertext. Returns `None` if the
+// ciphertext doesn't grow within `MAX_BLOCK_SIZE` bytes, as with a stream cipher.
+fn discover_block_size<F: Fn(&[u8]) -> Vec<u8>>(oracle: &F) -> Option<(usize, usize)> {
+    let initial_length = oracle(&[]).len();
+    for n in 1..=MAX_BLOCK_SIZE {
+        let length = oracle(&vec![0; n]).len();
+        if length > initial_length {
+            return Some((length - initial_length, initial_length.checked_sub(n)?));
+        }
+    }
+    None
+}
+
+// Recover the secret suffix an ECB oracle appends to its input, one byte at a time. Padding the
+// input so the next unknown byte is the last of a block, then matching that block against a
+// dictionary of all 256 candidates for it, reveals the byte. Returns `None` if the oracle isn't
+// ECB or a byte matches no candidate.
+pub fn recover_ecb_suffix<F: Fn(&[u8]) -> Vec<u8>>(oracle: F) -> Option<Vec<u8>> {
+    let (block_size, suffix_length) = discover_block_size(&oracle)?;
+    if crate::aes::repeated_blocks(&oracle(&vec![0; 3 * block_size]), block_size) == 0 {
+        return None;
+    }
+
+    let mut recovered = Vec::with_capacity(suffix_length);
+    while recovered.len() < suffix_length {
+        let padding = vec![0; block_size - 1 - recovered.len() % block_size];
+
+        // The last `block_size - 1` bytes before the unknown byte are all known.
+        let mut known = padding.clone();
+        known.extend_from_slice(&recovered);
+        let mut candidate = known[known.len() + 1 - block_size..].to_vec();
+        candidate.push(0);
+        let mut dictionary = HashMap::new();
+        for b in 0..=255 {
+            *candidate.last_mut().unwrap() = b;
+            dictionary.insert(oracle(&candidate).get(..block_size)?.to_vec(), b);
+        }
+
+        let block = recovered.len() / block_size;
+        let encrypted = oracle(&padding);
+        let target = encrypted.get(block * block_size..(block + 1) * block_size)?;
+        recovered.push(*dictionary.get(target)?);
+    }
+    Some(recovered)
+}
+
+/// How `PrefixedSuffixOracle` chooses the length of its random prefix.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PrefixLength {
+    /// One random length, chosen with the key.
+    FixedPerKey,
+    /// A new random length on every call.
+    PerCall,
+}
+
+const MAX_PREFIX_LENGTH: usize = 64;
+
+/// Like `SuffixOracle`, but also prepends a prefix of random bytes, which are new on every call.
+pub struct PrefixedSuffixOracle {
+    key: [u8; 16],
+    prefix_length: Option<usize>,
+    suffix: Vec<u8>,
+}
+
+impl PrefixedSuffixOracle {
+    pub fn new(suffix: &[u8], prefix_length: PrefixLength) -> Self {
+        PrefixedSuffixOracle {
+            key: random_key(),
+            prefix_length: match prefix_length {
+                PrefixLength::FixedPerKey => {
+                    Some(rand::thread_rng().gen_range(0..=MAX_PREFIX_LENGTH))
+                }
+                PrefixLength::PerCall => None,
+            },
+            suffix: suffix.to_vec(),
+        }
+    }
+
+    // The fixed prefix length, revealed so that measurements can be checked.
+    pub fn prefix_length(&self) -> Option<usize> {
+        self.prefix_length
+    }
+
+    pub fn encrypt(&self, bytes: &[u8]) -> Vec<u8> {
+        let prefix_length = self
+            .prefix_length
+            .unwrap_or_else(|| rand::thread_rng().gen_range(0..=MAX_PREFIX_LENGTH));
+        let mut plaintext: Vec<u8> = (0..prefix_length).map(|_| rand::random()).collect();
+        plaintext.extend_from_slice(bytes);
+        plaintext.extend_from_slice(&self.suffix);
+        encrypt_aes128_ecb(&plaintext, &self.key).expect("oracle key is 16 bytes")
+    }
+}
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+// Every ciphertext length is a multiple of the block size, so with a random prefix the greatest
+// common divisor of a few lengths finds it when growing the input can't.
+fn discover_block_size_with_prefix<F: Fn(&[u8]) -> Vec<u8>>(oracle: &F) -> usize {
+    (0..32).fold(0, |block_size, i| {
+        gcd(block_size, oracle(&vec![0; i]).len())
+    })
+}
+
+// Input that finds where the attacker's bytes start: guard bytes, two blocks of 0xaa, two blocks
+// of 0x55, then a separator block. The guard and separator differ from the neighbouring marker
+// bytes, so unless the markers start on a block boundary no two adjacent ciphertext blocks in
+// this region can be equal.
+const GUARD: u8 = 0x00;
+const MARKERS: [u8; 2] = [0xaa, 0x55];
+const SEPARATOR: u8 = 0xff;
+
+// Attempts `AlignedOracle` makes per call before giving up. Each attempt aligns the markers with
+// probability at least one in the block size, so an ECB oracle essentially never runs out.
+const MAX_ALIGNMENT_ATTEMPTS: usize = 1024;
+
+// Find the block size of an oracle that prepends unknown data, and check that it uses ECB. Three
+// blocks of each marker hold two whole blocks of it wherever the input starts, and only ECB
+// turns those into two pairs of repeated ciphertext blocks. Returns `None` for other modes, or
+// for lengths with no common factor, as from a stream cipher.
+fn discover_ecb_block_size_with_prefix<F: Fn(&[u8]) -> Vec<u8>>(oracle: &F) -> Option<usize> {
+    let block_size = discover_block_size_with_prefix(oracle);
+    if block_size <= 1 {
+        return None;
+    }
+    let mut input = Vec::new();
+    for marker in MARKERS {
+        input.resize(input.len() + 3 * block_size, marker);
+    }
+    (0..3)
+        .all(|_| crate::aes::repeated_blocks(&oracle(&input), block_size) >= 2)
+        .then_some(block_size)
+}
+
+/// Wraps an oracle that prepends unknown data, exposing one that doesn't: each call is retried,
+/// varying the amount of guard bytes, until the marker blocks are aligned, and the ciphertext up
+/// to the end of the separator is dropped. A call that can't be aligned returns no ciphertext and
+/// sets `failed`.
+struct AlignedOracle<F> {
+    oracle: F,
+    block_size: usize,
+    // The guard length to try first; it is kept once it works, so a fixed prefix length only
+    // needs searching once.
+    guard_length: Cell<usize>,
+    // The encrypted marker blocks, once seen.
+    encrypted_markers: RefCell<Option<[Vec<u8>; 2]>>,
+    failed: Cell<bool>,
+}
+
+impl<F: Fn(&[u8]) -> Vec<u8>> AlignedOracle<F> {
+    fn new(oracle: F, block_size: usize) -> Self {
+        AlignedOracle {
+            oracle,
+            block_size,
+            guard_length: Cell::new(1),
+            encrypted_markers: RefCell::new(None),
+            failed: Cell::new(false),
+        }
+    }
+
+    // Find two pairs of identical blocks at `j..j + 4` that hold the markers.
+    fn find_markers(&self, encrypted: &[u8]) -> Option<usize> {
+        let blocks: Vec<&[u8]> = encrypted.chunks(self.block_size).collect();
+        let mut known = self.encrypted_markers.borrow_mut();
+        let j = (0..blocks.len().saturating_sub(4)).find(|j| {
+            let [a, b, c, d] = [blocks[*j], blocks[j + 1], blocks[j + 2], blocks[j + 3]];
+            let pattern = a == b && c == d && b != c;
+            match known.as_ref() {
+                Some([first, second]) => pattern && a == first && c == second,
+                None => pattern,
+            }
+        })?;
+        known.get_or_insert_with(|| [blocks[j].to_vec(), blocks[j + 2].to_vec()]);
+        Some(j)
+    }
+
+    // Encrypt `payload` with the markers aligned. Returns the ciphertext, the index of the first
+    // marker block and the guard length used, or `None` if no attempt lined the markers up.
+    fn locate(&self, payload: &[u8]) -> Option<(Vec<u8>, usize, usize)> {
+        for _ in 0..MAX_ALIGNMENT_ATTEMPTS {
+            let guard_length = self.guard_length.get();
+            let mut input = vec![GUARD; guard_length];
+            for marker in MARKERS {
+                input.resize(input.len() + 2 * self.block_size, marker);
+            }
+            input.resize(input.len() + self.block_size, SEPARATOR);
+            input.extend_from_slice(payload);
+
+            let encrypted = (self.oracle)(&input);
+            if let Some(j) = self.find_markers(&encrypted) {
+                return Some((encrypted, j, guard_length));
+            }
+            self.guard_length.set(guard_length % self.block_size + 1);
+        }
+        None
+    }
+
+    fn encrypt(&self, payload: &[u8]) -> Vec<u8> {
+        match self.locate(payload) {
+            Some((encrypted, j, _)) => encrypted[(j + 5) * self.block_size..].to_vec(),
+            None => {
+                self.failed.set(true);
+                Vec::new()
+            }
+        }
+    }
+}
+
+// Measure the length of the data an ECB oracle prepends to its input, by finding how many guard
+// bytes align the marker blocks. With a per-call random prefix this is the length in one call.
+// Returns `None` if the oracle isn't ECB.
+pub fn measure_prefix_length<F: Fn(&[u8]) -> Vec<u8>>(oracle: F) -> Option<usize> {
+    let block_size = discover_ecb_block_size_with_prefix(&oracle)?;
+    let (_, j, guard_length) = AlignedOracle::new(oracle, block_size).locate(&[])?;
+    (j * block_size).checked_sub(guard_length)
+}
+
+// Recover the secret suffix of an ECB oracle that also prepends random data of fixed or varying
+// length, by running the byte-at-a-time attack on aligned calls only. Returns `None` if the
+// oracle isn't ECB.
+pub fn recover_ecb_suffix_with_prefix<F: Fn(&[u8]) -> Vec<u8>>(oracle: F) -> Option<Vec<u8>> {
+    let block_size = discover_ecb_block_size_with_prefix(&oracle)?;
+    let aligned = AlignedOracle::new(oracle, block_size);
+    let recovered = recover_ecb_suffix(|payload| aligned.encrypt(payload));
+    if aligned.failed.get() {
+        return None;
+    }
+    recovered
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProfileError {
+    Cipher(CipherError),
+    InvalidUtf8,
+    Cookie(CookieError),
+}
+
+impl fmt::Display for ProfileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProfileError::Cipher(error) => write!(f, "{}", error),
+            ProfileError::InvalidUtf8 => write!(f, "profile is not valid UTF-8"),
+            ProfileError::Cookie(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for ProfileError {}
+
+/// Issues user profiles encrypted under AES-128-ECB with a secret key, and decrypts and parses
+/// them again.
+pub struct ProfileService {
+    key: [u8; 16],
+    email_field: String,
+    uid_field: String,
+    role_field: String,
+}
+
+impl Default for ProfileService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProfileService {
+    pub fn new() -> Self {
+        Self::with_field_names("email", "uid", "role")
+    }
+
+    pub fn with_field_names(email: &str, uid: &str, role: &str) -> Self {
+        ProfileService {
+            key: random_key(),
+            email_field: email.to_string(),
+            uid_field: uid.to_string(),
+            role_field: role.to_string(),
+        }
+    }
+
+    // Encode `email=...&uid=10&role=user` and encrypt it. The `&` and `=` metacharacters are
+    // removed from the email address, so it can't add fields of its own.
+    pub fn profile_for(&self, email: &str) -> Vec<u8> {
+        let email: String = email.chars().filter(|c| *c != '&' && *c != '=').collect();
+        let mut profile = Cookie::new();
+        profile.insert(&self.email_field, &email);
+        profile.insert(&self.uid_field, "10");
+        profile.insert(&self.role_field, "user");
+        let encoded = crate::cookie::encode(&profile, Separator::Ampersand, Mode::Naive);
+        encrypt_aes128_ecb(encoded.as_bytes(), &self.key).expect("service key is 16 bytes")
+    }
+
+    pub fn parse_profile(&self, encrypted: &[u8]) -> Result<Cookie, ProfileError> {
+        let decrypted = decrypt_aes128_ecb(encrypted, &self.key).map_err(ProfileError::Cipher)?;
+        let decoded = String::from_utf8(decrypted).map_err(|_| ProfileError::InvalidUtf8)?;
+        crate::cookie::parse(&decoded, Separator::Ampersand, Mode::Naive)
+            .map_err(ProfileError::Cookie)
+    }
+
+    pub fn role_field(&self) -> &str {
+        &self.role_field
+    }
+}
+
+// Forge an encrypted profile with role `admin` from profiles `profile_for` issues, knowing only
+// that they end with the role `user`. Cut a profile where `user` starts the final block, and
+// paste on a block encrypting `admin` and its padding, taken from an email address aligned to
+// a block boundary. Returns `None` if the profiles aren't encrypted with ECB.
+pub fn forge_admin_profile<F: Fn(&str) -> Vec<u8>>(profile_for: F) -> Option<Vec<u8>> {
+    let oracle = |bytes: &[u8]| profile_for(&String::from_utf8_lossy(bytes));
+    let (block_size, fixed_length) = discover_block_size(&oracle)?;
+
+    // At this email length the profile fills whole blocks, so four more bytes push `user` into
+    // a block of its own.
+    let aligned_email_length = block_size - fixed_length % block_size;
+    let email = "A".repeat(aligned_email_length + "user".len());
+    let mut forged = profile_for(&email);
+    forged.truncate(forged.len().checked_sub(block_size)?);
+
+    // Find how many guard bytes put the start of the email's next block on a block boundary, by
+    // looking for two identical blocks.
+    let marker = "B".repeat(2 * block_size);
+    let (guard, j) = (0..block_size).find_map(|guard| {
+        let encrypted = profile_for(&("A".repeat(guard) + &marker));
+        let blocks: Vec<&[u8]> = encrypted.chunks(block_size).collect();
+        (0..blocks.len().saturating_sub(1))
+            .find(|j| blocks[*j] == blocks[j + 1])
+            .map(|j| (guard, j))
+    })?;
+
+    let mut admin = b"admin".to_vec();
+    Pkcs7.pad(&mut admin, block_size).ok()?;
+    let email = "A".repeat(guard) + std::str::from_utf8(&admin).ok()?;
+    let encrypted = profile_for(&email);
+    forged.extend_from_slice(encrypted.get(j * block_size..(j + 1) * block_size)?);
+    Some(forged)
+}
+
 #[cfg(test)]
 mod test {
     #[test]
@@ -11,8 +413,10 @@ mod test {
     fn challenge10_encrypt() {
         let key = "YELLOW SUBMARINE".as_bytes();
         let plaintext = "I'm back and I'm ringin' the bell".as_bytes();
-        let encrypted = crate::aes::encrypt_aes128_cbc(plaintext, key);
-        let decrypted = crate::aes::decrypt_aes128_cbc(&encrypted, key);
+        let encrypted =
+            crate::aes::encrypt_aes128_cbc(plaintext, key, &crate::aes::ZERO_IV).unwrap();
+        let decrypted =
+            crate::aes::decrypt_aes128_cbc(&encrypted, key, &crate::aes::ZERO_IV).unwrap();
         assert_eq!(plaintext, decrypted);
     }
 
@@ -20,8 +424,187 @@ mod test {
     fn challenge10_decrypt() {
         let file_contents = std::fs::read_to_string("data/10.txt").unwrap();
         let decoded = crate::encodings::base64_decode(&file_contents).unwrap();
-        let decrypted = crate::aes::decrypt_aes128_cbc(&decoded, "YELLOW SUBMARINE".as_bytes());
+        let decrypted = crate::aes::decrypt_aes128_cbc(
+            &decoded,
+            "YELLOW SUBMARINE".as_bytes(),
+            &crate::aes::ZERO_IV,
+        )
+        .unwrap();
         let plaintext = std::str::from_utf8(&decrypted).unwrap();
         assert!(plaintext.starts_with("I'm back and I'm ringin' the bell"));
     }
+
+    #[test]
+    fn challenge11() {
+        let trials = 5000;
+        let mut ecb_count = 0;
+        for _ in 0..trials {
+            let actual = std::cell::Cell::new(None);
+            let detected = crate::set2::detect_block_mode(|bytes| {
+                let (encrypted, mode) = crate::util::encryption_oracle_with_mode(bytes);
+                actual.set(Some(mode));
+                encrypted
+            });
+            assert_eq!(actual.get().unwrap(), detected);
+            if detected == crate::util::CipherMode::Ecb {
+                ecb_count += 1;
+            }
+        }
+        // Both modes should have been exercised.
+        assert!(ecb_count > 0 && ecb_count < trials);
+    }
+
+    #[test]
+    fn challenge12() {
+        let oracle =
+            crate::set2::SuffixOracle::from_base64(crate::set2::CHALLENGE12_SECRET).unwrap();
+        let recovered = crate::set2::recover_ecb_suffix(|bytes| oracle.encrypt(bytes)).unwrap();
+        assert_eq!(
+            "Rollin' in my 5.0\nWith my rag-top down so my hair can blow\n\
+             The girlies on standby waving just to say hi\nDid you stop? No, I just drove by\n",
+            std::str::from_utf8(&recovered).unwrap()
+        );
+    }
+
+    #[test]
+    fn ecb_suffix_lengths() {
+        // Suffixes ending on and around block boundaries, including the empty suffix.
+        for length in [0, 1, 15, 16, 17, 32, 47] {
+            let suffix: Vec<u8> = (0..length).map(|_| rand::random()).collect();
+            let oracle = crate::set2::SuffixOracle::new(&suffix);
+            assert_eq!(
+                Some(suffix),
+                crate::set2::recover_ecb_suffix(|bytes| oracle.encrypt(bytes))
+            );
+        }
+    }
+
+    #[test]
+    fn ecb_suffix_requires_ecb() {
+        let key = crate::util::random_key();
+        let cbc_oracle = |bytes: &[u8]| {
+            let mut plaintext = bytes.to_vec();
+            plaintext.extend_from_slice("secret".as_bytes());
+            crate::aes::encrypt_aes128_cbc(&plaintext, &key, &[7; 16]).unwrap()
+        };
+        assert_eq!(None, crate::set2::recover_ecb_suffix(cbc_oracle));
+    }
+
+    #[test]
+    fn ecb_suffix_with_prefix_requires_ecb() {
+        let key = crate::util::random_key();
+        let cbc_oracle = |bytes: &[u8]| {
+            let mut plaintext = vec![0; rand::random::<usize>() % 32];
+            plaintext.extend_from_slice(bytes);
+            plaintext.extend_from_slice("secret".as_bytes());
+            crate::aes::encrypt_aes128_cbc(&plaintext, &key, &[7; 16]).unwrap()
+        };
+        assert_eq!(
+            None,
+            crate::set2::recover_ecb_suffix_with_prefix(cbc_oracle)
+        );
+        assert_eq!(None, crate::set2::measure_prefix_length(cbc_oracle));
+
+        let ctr_oracle = |bytes: &[u8]| {
+            crate::aes::encrypt_aes128_ctr(bytes, &key, &[7; 8], crate::aes::CtrLayout::default())
+                .unwrap()
+        };
+        assert_eq!(
+            None,
+            crate::set2::recover_ecb_suffix_with_prefix(ctr_oracle)
+        );
+    }
+
+    #[test]
+    fn ecb_suffix_requires_growing_ciphertext() {
+        let key = crate::util::random_key();
+        let ctr_oracle = |bytes: &[u8]| {
+            crate::aes::encrypt_aes128_ctr(bytes, &key, &[7; 8], crate::aes::CtrLayout::default())
+                .unwrap()
+        };
+        assert_eq!(None, crate::set2::recover_ecb_suffix(ctr_oracle));
+        assert_eq!(
+            None,
+            crate::set2::recover_ecb_suffix(|_: &[u8]| vec![0; 16])
+        );
+    }
+
+    #[test]
+    fn challenge14() {
+        use crate::set2::{PrefixLength, PrefixedSuffixOracle};
+
+        let secret = crate::encodings::base64_decode(crate::set2::CHALLENGE12_SECRET).unwrap();
+        let oracle = PrefixedSuffixOracle::new(&secret, PrefixLength::FixedPerKey);
+        assert_eq!(
+            oracle.prefix_length(),
+            crate::set2::measure_prefix_length(|bytes| oracle.encrypt(bytes))
+        );
+        assert_eq!(
+            Some(secret),
+            crate::set2::recover_ecb_suffix_with_prefix(|bytes| oracle.encrypt(bytes))
+        );
+    }
+
+    #[test]
+    fn ecb_suffix_with_per_call_prefix() {
+        use crate::set2::{PrefixLength, PrefixedSuffixOracle};
+
+        let secret = "Did you stop? No, I just drove by".as_bytes();
+        let oracle = PrefixedSuffixOracle::new(secret, PrefixLength::PerCall);
+        assert_eq!(None, oracle.prefix_length());
+        assert!(crate::set2::measure_prefix_length(|bytes| oracle.encrypt(bytes)).unwrap() <= 64);
+        assert_eq!(
+            Some(secret.to_vec()),
+            crate::set2::recover_ecb_suffix_with_prefix(|bytes| oracle.encrypt(bytes))
+        );
+    }
+
+    #[test]
+    fn ecb_prefix_lengths() {
+        use crate::set2::{PrefixLength, PrefixedSuffixOracle};
+
+        for _ in 0..20 {
+            let oracle = PrefixedSuffixOracle::new(&[1, 2, 3], PrefixLength::FixedPerKey);
+            assert_eq!(
+                oracle.prefix_length(),
+                crate::set2::measure_prefix_length(|bytes| oracle.encrypt(bytes))
+            );
+        }
+    }
+
+    #[test]
+    fn challenge13() {
+        let service = crate::set2::ProfileService::new();
+        let profile = service
+            .parse_profile(&service.profile_for("foo@bar.com&role=admin"))
+            .unwrap();
+        assert_eq!(Some("foo@bar.comroleadmin"), profile.get("email"));
+        assert_eq!(Some("user"), profile.get("role"));
+
+        let forged = crate::set2::forge_admin_profile(|email| service.profile_for(email)).unwrap();
+        let profile = service.parse_profile(&forged).unwrap();
+        assert_eq!(Some("10"), profile.get("uid"));
+        assert_eq!(Some("admin"), profile.get("role"));
+    }
+
+    #[test]
+    fn cut_and_paste_with_other_field_names() {
+        for (email, uid, role) in [("e", "id", "r"), ("mail", "user_id", "privileges")] {
+            let service = crate::set2::ProfileService::with_field_names(email, uid, role);
+            let forged =
+                crate::set2::forge_admin_profile(|email| service.profile_for(email)).unwrap();
+            let profile = service.parse_profile(&forged).unwrap();
+            assert_eq!(Some("admin"), profile.get(service.role_field()));
+        }
+    }
+
+    #[test]
+    fn cut_and_paste_requires_ecb() {
+        let key = crate::util::random_key();
+        let cbc_profile_for = |email: &str| {
+            let profile = format!("email={}&uid=10&role=user", email);
+            crate::aes::encrypt_aes128_cbc(profile.as_bytes(), &key, &[7; 16]).unwrap()
+        };
+        assert_eq!(None, crate::set2::forge_admin_profile(cbc_profile_for));
+    }
 }
@@ -0,0 +1,63 @@
+use crate::aes::{ctr_edit, encrypt_aes128_ctr, CtrLayout};
+use crate::util::random_key;
+
+/// A CTR ciphertext under a secret key and nonce, with an API that lets callers seek into it and
+/// overwrite the plaintext.
+pub struct EditOracle {
+    key: [u8; 16],
+    nonce: [u8; 8],
+    ciphertext: Vec<u8>,
+}
+
+impl EditOracle {
+    pub fn new(plaintext: &[u8]) -> Self {
+        let key = random_key();
+        let nonce = rand::random();
+        EditOracle {
+            key,
+            nonce,
+            ciphertext: encrypt_aes128_ctr(plaintext, &key, &nonce, CtrLayout::default())
+                .expect("oracle key is 16 bytes"),
+        }
+    }
+
+    pub fn ciphertext(&self) -> &[u8] {
+        &self.ciphertext
+    }
+
+    // Re-encrypt `ciphertext` with `new_plaintext` written at `offset`, without revealing the
+    // key.
+    pub fn edit(&self, ciphertext: &[u8], offset: usize, new_plaintext: &[u8]) -> Vec<u8> {
+        ctr_edit(ciphertext, &self.key, &self.nonce, offset, new_plaintext)
+            .expect("oracle key is 16 bytes")
+    }
+}
+
+// Recover the plaintext of a CTR ciphertext given an exposed edit function. Overwriting the
+// whole plaintext with zeros makes the edit function return the raw keystream, which XORed with
+// the original ciphertext gives the plaintext.
+pub fn recover_plaintext_via_edit<F>(ciphertext: &[u8], edit: F) -> Vec<u8>
+where
+    F: Fn(&[u8], usize, &[u8]) -> Vec<u8>,
+{
+    let keystream = edit(ciphertext, 0, &vec![0; ciphertext.len()]);
+    crate::util::xor_buffers(ciphertext, &keystream)
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn challenge25() {
+        let base64 = std::fs::read_to_string("data/7.txt").unwrap();
+        let bytes = crate::encodings::base64_decode(&base64).unwrap();
+        let plaintext =
+            crate::aes::decrypt_aes128_ecb(&bytes, "YELLOW SUBMARINE".as_bytes()).unwrap();
+
+        let oracle = crate::set4::EditOracle::new(&plaintext);
+        let recovered =
+            crate::set4::recover_plaintext_via_edit(oracle.ciphertext(), |c, offset, p| {
+                oracle.edit(c, offset, p)
+            });
+        assert_eq!(plaintext, recovered);
+    }
+}
@@ -1,11 +1,13 @@
 use rand::Rng;
 
+use crate::padding::PaddingError;
+
 // Find the Hamming distance between the specified slices.
 pub fn hamming_distance(s1: &[u8], s2: &[u8]) -> u32 {
     s1.iter()
         .zip(s2.iter())
         .map(|e| (e.0 ^ e.1).count_ones())
-        .fold(0, |acc, x| acc + x)
+        .sum()
 }
 
 // TODO: Make this more declarative/functional
@@ -18,9 +20,7 @@ pub fn transpose(bytes: &[u8], block_size: usize) -> Vec<Vec<u8>> {
                 if let Some(block) = transposed.get_mut(i) {
                     block.push(chunk[i]);
                 } else {
-                    let mut block = Vec::new();
-                    block.push(chunk[i]);
-                    transposed.insert(i, block);
+                    transposed.insert(i, vec![chunk[i]]);
                 }
             }
         }
@@ -40,42 +40,71 @@ pub fn xor_vec(v: &[u8], key: u8) -> Vec<u8> {
 
 // Pad the specified data using PKCS #7.
 pub fn pkcs7_pad(bytes: &mut Vec<u8>, block_length: u8) {
-    if bytes.len() == 0 {
+    if bytes.is_empty() {
         return;
     }
 
-    let padding: Vec<u8>;
     let modulo = bytes.len() % (block_length as usize);
-    if modulo == 0 {
-        padding = vec![block_length; block_length as usize];
+    let padding = if modulo == 0 {
+        vec![block_length; block_length as usize]
     } else {
         let num_padding_bytes = block_length - (modulo as u8);
-        padding = vec![num_padding_bytes as u8; num_padding_bytes as usize];
-    }
+        vec![num_padding_bytes; num_padding_bytes as usize]
+    };
     bytes.extend_from_slice(&padding);
 }
 
+// Remove as many bytes as the last byte says, without any validation, like a broken server
+// would. Data shorter than the padding length is emptied rather than causing a panic.
 pub fn pkcs7_unpad(bytes: &mut Vec<u8>) {
-    if let Some(padding_length) = bytes.iter().last() {
-        let mut num_to_remove = padding_length.clone();
-        while num_to_remove > 0 {
-            bytes.pop().expect("Vec should not be empty");
-            num_to_remove -= 1;
-        }
+    if let Some(padding_length) = bytes.last() {
+        bytes.truncate(bytes.len().saturating_sub(*padding_length as usize));
+    }
+}
+
+// Remove PKCS #7 padding, checking that the data is a whole number of blocks, that the padding
+// length is in 1..=block_length, and that every padding byte holds it.
+pub fn pkcs7_unpad_strict(bytes: &mut Vec<u8>, block_length: u8) -> Result<(), PaddingError> {
+    let block_length = block_length as usize;
+    if bytes.is_empty() || !bytes.len().is_multiple_of(block_length) {
+        return Err(PaddingError::NotBlockAligned(bytes.len()));
     }
+    let padding_length = bytes[bytes.len() - 1];
+    if padding_length == 0 || padding_length as usize > block_length {
+        return Err(PaddingError::InvalidPaddingLength(padding_length));
+    }
+    let start = bytes.len() - padding_length as usize;
+    if let Some(i) = bytes[start..].iter().position(|b| *b != padding_length) {
+        return Err(PaddingError::InvalidPaddingByte { offset: start + i });
+    }
+    bytes.truncate(start);
+    Ok(())
 }
 
 /// Generate a random AES-128 key.
 pub fn random_key() -> [u8; 16] {
     let mut key = [0; 16];
-    for i in 0..key.len() {
-        key[i] = rand::random();
+    for b in key.iter_mut() {
+        *b = rand::random();
     }
     key
 }
 
+/// The block cipher mode chosen by `encryption_oracle_with_mode`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CipherMode {
+    Ecb,
+    Cbc,
+}
+
 /// Add a random number of bytes between 5-10 (inclusive)
 pub fn encryption_oracle(bytes: &[u8]) -> Vec<u8> {
+    encryption_oracle_with_mode(bytes).0
+}
+
+// Like `encryption_oracle`, but also reveal which mode was used, so that mode detection can be
+// scored.
+pub fn encryption_oracle_with_mode(bytes: &[u8]) -> (Vec<u8>, CipherMode) {
     let key = random_key();
     let mut padded_bytes = Vec::new();
     for _i in 0..rand::thread_rng().gen_range(5..=10) {
@@ -85,12 +114,19 @@ pub fn encryption_oracle(bytes: &[u8]) -> Vec<u8> {
     for _i in 0..rand::thread_rng().gen_range(5..=10) {
         padded_bytes.push(rand::random());
     }
-    let encrypted = if rand::random() {
-        crate::aes::encrypt_aes128_cbc(&padded_bytes, &key)
+    let (encrypted, mode) = if rand::random() {
+        let iv: [u8; 16] = rand::random();
+        (
+            crate::aes::encrypt_aes128_cbc(&padded_bytes, &key, &iv),
+            CipherMode::Cbc,
+        )
     } else {
-        crate::aes::encrypt_aes128_ecb(&padded_bytes, &key)
+        (
+            crate::aes::encrypt_aes128_ecb(&padded_bytes, &key),
+            CipherMode::Ecb,
+        )
     };
-    encrypted
+    (encrypted.expect("random_key is 16 bytes"), mode)
 }
 
 #[cfg(test)]
@@ -177,4 +213,53 @@ mod test {
         crate::util::pkcs7_unpad(&mut bytes);
         assert_eq!([0x1, 0x2, 0x3, 0x4].to_vec(), bytes);
     }
+
+    #[test]
+    fn unpad_lenient_short_data() {
+        let mut bytes = vec![0x41, 0x20];
+        crate::util::pkcs7_unpad(&mut bytes);
+        let empty: Vec<u8> = Vec::new();
+        assert_eq!(empty, bytes);
+    }
+
+    #[test]
+    fn unpad_strict() {
+        let mut bytes = vec![0x1, 0x2, 0x3, 0x4, 0x4, 0x4, 0x4, 0x4];
+        crate::util::pkcs7_unpad_strict(&mut bytes, 8).unwrap();
+        assert_eq!([0x1, 0x2, 0x3, 0x4].to_vec(), bytes);
+
+        let mut bytes = vec![0x8; 8];
+        crate::util::pkcs7_unpad_strict(&mut bytes, 8).unwrap();
+        assert!(bytes.is_empty());
+    }
+
+    #[test]
+    fn unpad_strict_errors() {
+        use crate::padding::PaddingError;
+
+        let cases: [(&[u8], PaddingError); 6] = [
+            (&[], PaddingError::NotBlockAligned(0)),
+            (&[0x1, 0x2, 0x3, 0x1], PaddingError::NotBlockAligned(4)),
+            (&[0x41; 8], PaddingError::InvalidPaddingLength(0x41)),
+            (
+                &[0x1, 0x2, 0x3, 0x4, 0x5, 0x6, 0x7, 0x0],
+                PaddingError::InvalidPaddingLength(0),
+            ),
+            (
+                &[0x1, 0x2, 0x3, 0x4, 0x4, 0x3, 0x4, 0x4],
+                PaddingError::InvalidPaddingByte { offset: 5 },
+            ),
+            (
+                &[0x1, 0x2, 0x3, 0x4, 0x5, 0x3, 0x2, 0x3],
+                PaddingError::InvalidPaddingByte { offset: 6 },
+            ),
+        ];
+        for (bytes, expected) in cases {
+            let mut bytes = bytes.to_vec();
+            assert_eq!(
+                Err(expected),
+                crate::util::pkcs7_unpad_strict(&mut bytes, 8)
+            );
+        }
+    }
 }